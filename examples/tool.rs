@@ -16,10 +16,11 @@ fn main() {
 	let f_rdr = File::open(file_path).unwrap();
 	let mut rdr = CafChunkReader::new(f_rdr).unwrap();
 	// Dump the decoded packets.
-	loop {
-		let chunk = rdr.read_chunk().unwrap();
+	while let Some(hdr) = rdr.read_chunk_header_opt().unwrap() {
+		let chunk = rdr.read_chunk_body(&hdr).unwrap();
 		match chunk {
-			CafChunk::AudioDataInMemory(..) => println!("Audio data in memory"),
+			CafChunk::AudioDataInMemory(ref d) =>
+				println!("Audio data in memory: {} bytes", d.data.len()),
 			_ => println!("{:?}", chunk),
 		}
 	}
@@ -11,12 +11,21 @@ CAF chunk decoding
 */
 
 use ::CafError;
-// TODO once we drop compat for pre rust 1.15 replace this with "use ::Read;"
-use std::io::Read;
-// TODO once we drop compat for pre rust 1.15 replace this with "use ::IoError;"
-use std::io::Error as IoError;
 use ::ChunkType;
 use ::FormatType;
+use ::ChannelLayoutTag;
+use ::ChannelLabel;
+use vlq::{read_vlq, write_vlq, vlq_width};
+use spec::{FLAG_IS_FLOAT, FLAG_IS_LITTLE_ENDIAN};
+use io::Read;
+use alloc::vec::Vec;
+use alloc::string::{String, ToString};
+use core::cmp;
+use core::fmt::{self, Display};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
 
 /// A decoded CAF chunk header
 #[derive(Debug, Clone)]
@@ -33,15 +42,35 @@ pub struct CafChunkHeader {
 /// An in-memory CAF chunk.
 ///
 /// The list represents the chunk types we can parse.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum CafChunk {
 	Desc(AudioDescription),
-	AudioDataInMemory(u32, Vec<u8>),
+	AudioDataInMemory(AudioData),
 	PacketTable(PacketTable),
 	ChanLayout(ChannelLayout),
 	MagicCookie(Vec<u8>),
 	// ...
-	Info(Vec<(String, String)>), // TODO use a hash map
+	Markers(Markers),
+	Regions(Regions),
+	// ...
+	/// The strings referenced by other chunks, by their string id
+	Strings(Vec<(u32, String)>),
+	// ...
+	Overview(Overview),
+	// ...
+	/// The comments entries, in file order. Encoded like the `Info` chunk.
+	EditComments(Vec<(String, String)>),
+	/// The entries, in file order. See `info_map` for lookups by key.
+	Info(Vec<(String, String)>),
+	/// The SMPTE UMID of the audio, as raw bytes
+	///
+	/// The UMID is 64 bytes long, shorter chunks are rejected by `decode_chunk`.
+	UniqueMaterialIdentifier(Vec<u8>),
+	/// Private data of third party tools, identified by a UUID
+	UserDefined {
+		uuid :[u8; 16],
+		data :Vec<u8>,
+	},
 	// ...
 }
 
@@ -54,12 +83,80 @@ impl CafChunk {
 			&CafChunk::PacketTable(..) => PacketTable,
 			&CafChunk::ChanLayout(..) => ChannelLayout,
 			&CafChunk::MagicCookie(..) => MagicCookie,
+			&CafChunk::Markers(..) => Marker,
+			&CafChunk::Regions(..) => Region,
+			&CafChunk::Strings(..) => Strings,
+			&CafChunk::Overview(..) => Overview,
+			&CafChunk::EditComments(..) => EditComments,
 			&CafChunk::Info(..) => Info,
+			&CafChunk::UniqueMaterialIdentifier(..) => UniqueMaterialIdentifier,
+			&CafChunk::UserDefined { .. } => UserDefined,
+		}
+	}
+	/// Returns the content of an audio data chunk that was read to memory
+	///
+	/// Returns `None` for chunks other than `AudioDataInMemory`.
+	pub fn audio_data(&self) -> Option<&AudioData> {
+		match self {
+			&CafChunk::AudioDataInMemory(ref audio_data) => Some(audio_data),
+			_ => None,
+		}
+	}
+	/// Creates an `AudioDataInMemory` chunk from its parts
+	///
+	/// The variant used to be a tuple of the edit count and the data.
+	/// This constructor is kept for code written against that form.
+	#[deprecated(note = "use `CafChunk::AudioDataInMemory(AudioData::new(..))` instead")]
+	pub fn audio_data_in_memory(edit_count :u32, data :Vec<u8>) -> CafChunk {
+		CafChunk::AudioDataInMemory(AudioData::new(edit_count, data))
+	}
+	/// Returns the entries of an info chunk as a map
+	///
+	/// If a key occurs multiple times, the last value is kept.
+	/// Returns `None` for chunks other than `Info`.
+	/// Only available with the `std` feature.
+	#[cfg(feature = "std")]
+	pub fn info_map(&self) -> Option<HashMap<String, String>> {
+		match self {
+			&CafChunk::Info(ref entries) => Some(entries.iter().cloned().collect()),
+			_ => None,
 		}
 	}
+	/// Returns the markers sorted by their frame position
+	///
+	/// The spec doesn't require markers to be stored in order.
+	/// Markers with equal positions are sorted by their id, and
+	/// otherwise keep their order in the file.
+	/// Markers with a NaN position come last, again sorted by their id.
+	/// For chunks other than `Markers`, an empty list is returned.
+	pub fn markers_by_time(&self) -> Vec<&CafMarker> {
+		let mut res :Vec<&CafMarker> = match self {
+			&CafChunk::Markers(ref m) => m.markers.iter().collect(),
+			_ => Vec::new(),
+		};
+		res.sort_by(|a, b| {
+			use core::cmp::Ordering;
+			// NaN positions sort last to keep the order total
+			let (pa, pb) = (a.frame_position, b.frame_position);
+			let ord = match (pa.is_nan(), pb.is_nan()) {
+				(false, false) => pa.partial_cmp(&pb).unwrap(),
+				(false, true) => Ordering::Less,
+				(true, false) => Ordering::Greater,
+				(true, true) => Ordering::Equal,
+			};
+			ord.then(a.marker_id.cmp(&b.marker_id))
+		});
+		res
+	}
 }
 
-#[derive(Debug, Clone)]
+/// The format description of the audio data
+///
+/// Equality compares the `sample_rate` as a float, so a NaN sample
+/// rate is never equal to anything. Compare the `raw_sample_rate_bits`
+/// if a bit-for-bit comparison is needed.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AudioDescription {
 	pub sample_rate :f64,
 	pub format_id :FormatType,
@@ -70,8 +167,227 @@ pub struct AudioDescription {
 	pub bits_per_channel :u32,
 }
 
+impl AudioDescription {
+	/// Creates a description for interleaved 16 bit little endian Linear PCM
+	pub fn pcm_s16le(sample_rate :f64, channels :u32) -> Self {
+		AudioDescription::pcm(sample_rate, channels, 16, FLAG_IS_LITTLE_ENDIAN)
+	}
+	/// Creates a description for interleaved 32 bit little endian float Linear PCM
+	pub fn pcm_f32(sample_rate :f64, channels :u32) -> Self {
+		AudioDescription::pcm(sample_rate, channels, 32,
+			FLAG_IS_FLOAT | FLAG_IS_LITTLE_ENDIAN)
+	}
+	fn pcm(sample_rate :f64, channels :u32, bits :u32, flags :u32) -> Self {
+		AudioDescription {
+			sample_rate : sample_rate,
+			format_id : FormatType::LinearPcm,
+			format_flags : flags,
+			bytes_per_packet : bits / 8 * channels,
+			frames_per_packet : 1,
+			channels_per_frame : channels,
+			bits_per_channel : bits,
+		}
+	}
+	/// Returns whether the samples are floats
+	///
+	/// Always returns `false` for formats other than Linear PCM.
+	pub fn pcm_is_float(&self) -> bool {
+		self.format_id == FormatType::LinearPcm &&
+			self.format_flags & FLAG_IS_FLOAT != 0
+	}
+	/// Returns whether the samples are little endian
+	///
+	/// Always returns `false` for formats other than Linear PCM.
+	pub fn pcm_is_little_endian(&self) -> bool {
+		self.format_id == FormatType::LinearPcm &&
+			self.format_flags & FLAG_IS_LITTLE_ENDIAN != 0
+	}
+	/// Returns whether the audio is stored in a compressed format
+	///
+	/// Only Linear PCM with one frame per packet counts as uncompressed.
+	pub fn is_compressed(&self) -> bool {
+		self.format_id != FormatType::LinearPcm || self.frames_per_packet != 1
+	}
+	/// Returns the number of bytes of a single frame of Linear PCM audio
+	///
+	/// If the samples are stored in larger containers, e.g. 24 bit samples
+	/// in 4 bytes, the size of the containers is taken from `bytes_per_packet`.
+	/// Returns `None` for other formats, or if `bits_per_channel` is zero.
+	pub fn bytes_per_frame(&self) -> Option<u32> {
+		if self.format_id != FormatType::LinearPcm || self.bits_per_channel == 0 {
+			return None;
+		}
+		if self.frames_per_packet == 1 && self.bytes_per_packet != 0 {
+			return Some(self.bytes_per_packet);
+		}
+		Some(self.channels_per_frame * self.bits_per_channel / 8)
+	}
+	/// Returns the bits of the sample rate as stored in the file
+	///
+	/// Useful for writers that want to reproduce the
+	/// exact bytes of the audio description.
+	pub fn raw_sample_rate_bits(&self) -> u64 {
+		use byteorder::{BigEndian, ByteOrder};
+		let mut buf = [0; 8];
+		BigEndian::write_f64(&mut buf, self.sample_rate);
+		BigEndian::read_u64(&buf)
+	}
+	/// Returns the number of frames per packet for MPEG-4 AAC
+	///
+	/// Returns `None` if the format is not `Mpeg4Aac`.
+	///
+	/// The AAC frame lengths are 1024 and 960 frames (for
+	/// the core codec), 2048 and 1920 (with SBR), and 512 and 480 frames
+	/// (for the low delay profiles).
+	/// If `frames_per_packet` holds one of these values, it is returned.
+	/// If it is zero or holds some other value, the frame length
+	/// is derived from `format_flags`, which, per CoreAudio convention,
+	/// holds the MPEG-4 audio object type for AAC:
+	///
+	/// * 5 (SBR, HE-AAC) and 29 (PS, HE-AACv2) yield 2048
+	/// * 23 (LD) and 39 (ELD) yield 512
+	/// * all other object types yield the default of 1024
+	///
+	/// Note that the 960 and 480 frame variants can't be told
+	/// apart from their 1024 and 512 frame counterparts through the
+	/// object type, so they are only returned if `frames_per_packet`
+	/// says so.
+	pub fn aac_frames_per_packet(&self) -> Option<u32> {
+		if self.format_id != FormatType::Mpeg4Aac {
+			return None;
+		}
+		match self.frames_per_packet {
+			480 | 512 | 960 | 1024 | 1920 | 2048 => return Some(self.frames_per_packet),
+			_ => (),
+		}
+		Some(match self.format_flags {
+			5 | 29 => 2048,
+			23 | 39 => 512,
+			_ => 1024,
+		})
+	}
+	/// Returns the bits per channel, falling back to a default if unset
+	///
+	/// Descriptions of compressed formats often leave `bits_per_channel`
+	/// at zero. If it is nonzero, it is returned as-is, otherwise a
+	/// default for the format is returned:
+	///
+	/// * Linear PCM: derived from `bytes_per_packet`, or 16 if that
+	///   isn't possible
+	/// * Apple Lossless: the source bit depth from `format_flags`
+	///   (1 to 4 meaning 16, 20, 24 and 32 bits), or 16
+	/// * MACE 3:1 and 6:1: 8, their decoded sample size
+	/// * all others, including µ-law and A-law: 16, the
+	///   size of the decoded samples
+	///
+	/// The returned value is never zero.
+	pub fn effective_bits_per_channel(&self) -> u32 {
+		self.decoded_bits_per_channel().unwrap_or(16)
+	}
+	/// Checks the description for obvious inconsistencies
+	///
+	/// Returns `CafError::InvalidAudioDescription` if the sample rate
+	/// is not positive, if there are no channels, or if a Linear PCM
+	/// description doesn't give the (constant) packet size.
+	/// Whether a packet table is present for formats
+	/// that need one is not checked here.
+	pub fn validate(&self) -> Result<(), CafError> {
+		let invalid = |reason| Err(CafError::InvalidAudioDescription(reason));
+		if !(self.sample_rate > 0.0) {
+			return invalid("The sample rate is not positive");
+		}
+		if self.channels_per_frame == 0 {
+			return invalid("The number of channels is zero");
+		}
+		if self.format_id == FormatType::LinearPcm &&
+				(self.bytes_per_packet == 0 || self.frames_per_packet == 0) {
+			return invalid("Linear PCM needs a constant packet size");
+		}
+		Ok(())
+	}
+	/// Returns the bit depth of the decoded samples, if known
+	///
+	/// Like `effective_bits_per_channel`, but returns `None` instead of
+	/// a default if neither the description nor the format determine
+	/// the bit depth, e.g. for AAC.
+	pub fn decoded_bits_per_channel(&self) -> Option<u32> {
+		if self.bits_per_channel != 0 {
+			return Some(self.bits_per_channel);
+		}
+		match self.format_id {
+			FormatType::LinearPcm => {
+				let bytes_per_sample = self.frames_per_packet
+					.checked_mul(self.channels_per_frame)
+					.and_then(|v| self.bytes_per_packet.checked_div(v));
+				match bytes_per_sample {
+					Some(v) if v > 0 => Some(v * 8),
+					_ => None,
+				}
+			},
+			FormatType::AppleLossless => match self.format_flags {
+				1 => Some(16),
+				2 => Some(20),
+				3 => Some(24),
+				4 => Some(32),
+				_ => None,
+			},
+			FormatType::Mace3 | FormatType::Mace6 => Some(8),
+			FormatType::Ulaw | FormatType::Alaw => Some(16),
+			_ => None,
+		}
+	}
+}
 
-#[derive(Debug, Clone)]
+
+impl Display for AudioDescription {
+	/// Writes a short summary, like `48000 Hz, 2 ch, lpcm (16-bit), 4 bytes/packet`
+	///
+	/// The bit depth is omitted if it isn't known, and the number
+	/// of frames per packet is only given if it isn't one.
+	fn fmt(&self, f :&mut fmt::Formatter) -> Result<(), fmt::Error> {
+		try!(write!(f, "{} Hz, {} ch, ", self.sample_rate, self.channels_per_frame));
+		try!(write!(f, "{}", self.format_id.to_string().trim_end()));
+		if let Some(bits) = self.decoded_bits_per_channel() {
+			try!(write!(f, " ({}-bit)", bits));
+		}
+		match self.bytes_per_packet {
+			0 => try!(write!(f, ", variable bytes/packet")),
+			v => try!(write!(f, ", {} bytes/packet", v)),
+		}
+		match self.frames_per_packet {
+			1 => (),
+			0 => try!(write!(f, ", variable frames/packet")),
+			v => try!(write!(f, ", {} frames/packet", v)),
+		}
+		Ok(())
+	}
+}
+
+/// The content of an audio data chunk, read into memory
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioData {
+	/// Number of edits made to the file, used to determine
+	/// whether dependent chunks like overviews are still valid
+	pub edit_count :u32,
+	/// The packets, concatenated
+	pub data :Vec<u8>,
+}
+
+impl AudioData {
+	pub fn new(edit_count :u32, data :Vec<u8>) -> Self {
+		AudioData {
+			edit_count : edit_count,
+			data : data,
+		}
+	}
+	/// Returns the edit count and the data, like the former tuple variant
+	pub fn into_parts(self) -> (u32, Vec<u8>) {
+		(self.edit_count, self.data)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PacketTable {
 	pub num_valid_frames :i64,
 	pub num_priming_frames :i32,
@@ -79,22 +395,275 @@ pub struct PacketTable {
 	pub lengths :Vec<u64>,
 }
 
-#[derive(Debug, Clone)]
+impl PacketTable {
+	/// Returns the average number of frames per packet
+	///
+	/// The average is computed from the frame counts stored in the
+	/// table, counting priming and remainder frames as well, as
+	/// they are part of the encoded packets.
+	/// This is useful as a sanity check, or if the audio description
+	/// doesn't specify the number of frames per packet.
+	///
+	/// Returns `None` if the table is empty or the stored frame
+	/// counts are negative.
+	pub fn average_frames_per_packet(&self) -> Option<f64> {
+		if self.lengths.len() == 0 || self.num_valid_frames < 0 ||
+				self.num_priming_frames < 0 || self.num_remainder_frames < 0 {
+			return None;
+		}
+		let total_frames = self.num_valid_frames +
+			self.num_priming_frames as i64 + self.num_remainder_frames as i64;
+		Some(total_frames as f64 / self.lengths.len() as f64)
+	}
+	/// Checks the stored frame counts for consistency with the packet count
+	///
+	/// Every packet holds `frames_per_packet` frames, so the sum of the
+	/// valid, priming and remainder frames should equal the number
+	/// of packets times `frames_per_packet`.
+	/// Returns that product minus the sum. Any value other than zero
+	/// indicates a buggy encoder or truncated audio data, so
+	/// trimming the priming and remainder frames would give wrong results.
+	///
+	/// Returns `None` if `frames_per_packet` is zero, as then the
+	/// number of frames of a packet is not known.
+	pub fn frame_count_discrepancy(&self, frames_per_packet :u32) -> Option<i64> {
+		if frames_per_packet == 0 {
+			return None;
+		}
+		let packet_frames = self.lengths.len() as i64 * frames_per_packet as i64;
+		let stored_frames = self.num_valid_frames +
+			self.num_priming_frames as i64 + self.num_remainder_frames as i64;
+		Some(packet_frames - stored_frames)
+	}
+	/// Returns the number of bytes the length of each packet occupies
+	///
+	/// The widths are computed from the lengths, assuming the minimal
+	/// encoding, which is also what `encode_chunk` writes.
+	/// Their sum plus the 24 byte header is the size of the chunk if
+	/// the encoder used the minimal encoding, so comparing it to the
+	/// chunk size in the file tells whether the lengths were padded.
+	pub fn vlq_widths(&self) -> Vec<u8> {
+		self.lengths.iter().map(|&l| vlq_width(l)).collect()
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ChannelLayout {
-	// TODO enrich this one and the one below with some meaning
-	// e.g. we'll maybe need some other representation, like an enum?
+	/// The raw tag, see `layout_tag` for a decoded version
 	pub channel_layout_tag :u32,
+	// TODO enrich this one with some meaning
+	// e.g. we'll maybe need some other representation, like an enum?
 	pub channel_bitmap :u32,
 	pub channel_descriptions :Vec<ChannelDescription>,
 }
 
-#[derive(Debug, Clone)]
+impl ChannelLayout {
+	/// Returns the decoded channel layout tag
+	pub fn layout_tag(&self) -> ChannelLayoutTag {
+		ChannelLayoutTag::from(self.channel_layout_tag)
+	}
+	/// Returns the number of channel descriptions
+	pub fn description_count(&self) -> usize {
+		self.channel_descriptions.len()
+	}
+	/// Returns whether the layout contains channel descriptions
+	///
+	/// Layouts described by the tag or the bitmap usually don't
+	/// contain any.
+	pub fn has_descriptions(&self) -> bool {
+		self.channel_descriptions.len() > 0
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ChannelDescription {
+	/// The raw label, see `label` for a decoded version
 	pub channel_label :u32,
 	pub channel_flags :u32,
 	pub coordinates :(f32, f32, f32),
 }
 
+impl ChannelDescription {
+	/// Returns the decoded channel label
+	pub fn label(&self) -> ChannelLabel {
+		ChannelLabel::from(self.channel_label)
+	}
+}
+
+/// The ALACSpecificConfig stored in the magic cookie of Apple Lossless files
+///
+/// Obtain it via `parse_alac_cookie`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlacConfig {
+	/// The number of frames per packet
+	pub frame_length :u32,
+	pub compatible_version :u8,
+	pub bit_depth :u8,
+	pub pb :u8,
+	pub mb :u8,
+	pub kb :u8,
+	pub num_channels :u8,
+	pub max_run :u16,
+	pub max_frame_bytes :u32,
+	pub avg_bit_rate :u32,
+	pub sample_rate :u32,
+}
+
+/// Parses the magic cookie of an Apple Lossless file
+///
+/// The cookie may be wrapped into "frma" and "alac" atoms,
+/// those are skipped.
+/// Returns `None` if the cookie is too short.
+pub fn parse_alac_cookie(cookie :&[u8]) -> Option<AlacConfig> {
+	use byteorder::{BigEndian as Be, ByteOrder};
+	let mut cookie = cookie;
+	for atom in [b"frma", b"alac"].iter() {
+		if cookie.len() >= 12 && &cookie[4..8] == &atom[..] {
+			cookie = &cookie[12..];
+		}
+	}
+	if cookie.len() < 24 {
+		return None;
+	}
+	Some(AlacConfig {
+		frame_length : Be::read_u32(&cookie[0..]),
+		compatible_version : cookie[4],
+		bit_depth : cookie[5],
+		pb : cookie[6],
+		mb : cookie[7],
+		kb : cookie[8],
+		num_channels : cookie[9],
+		max_run : Be::read_u16(&cookie[10..]),
+		max_frame_bytes : Be::read_u32(&cookie[12..]),
+		avg_bit_rate : Be::read_u32(&cookie[16..]),
+		sample_rate : Be::read_u32(&cookie[20..]),
+	})
+}
+
+/// The markers of a file, e.g. cue points
+#[derive(Debug, Clone, PartialEq)]
+pub struct Markers {
+	/// The kind of SMPTE time used by the markers
+	pub smpte_time_type :u32,
+	pub markers :Vec<CafMarker>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CafMarker {
+	pub marker_type :u32,
+	/// The position of the marker in frames
+	pub frame_position :f64,
+	pub marker_id :u32,
+	pub smpte_time :SmpteTime,
+	/// The channel the marker refers to, or 0 for all channels
+	pub channel :u32,
+}
+
+/// A time in SMPTE format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmpteTime {
+	pub hours :i8,
+	pub minutes :i8,
+	pub seconds :i8,
+	pub frames :i8,
+	pub sub_frame_sample_offset :u32,
+}
+
+/// The regions of a file, e.g. loops
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regions {
+	/// The kind of SMPTE time used by the markers of the regions
+	pub smpte_time_type :u32,
+	pub regions :Vec<CafRegion>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CafRegion {
+	pub region_id :u32,
+	pub flags :RegionFlags,
+	/// The markers delimiting the region
+	pub markers :Vec<CafMarker>,
+}
+
+impl CafRegion {
+	/// Returns whether the region is a loop
+	pub fn is_loop(&self) -> bool {
+		self.flags.loop_enabled()
+	}
+}
+
+/// The flags of a region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionFlags(pub u32);
+
+impl RegionFlags {
+	/// Whether the region should be played in a loop
+	pub fn loop_enabled(&self) -> bool {
+		self.0 & 1 != 0
+	}
+	/// Whether the region should be played forward
+	pub fn play_forward(&self) -> bool {
+		self.0 & 2 != 0
+	}
+	/// Whether the region should be played backward
+	pub fn play_backward(&self) -> bool {
+		self.0 & 4 != 0
+	}
+}
+
+/// Overview of the audio data, e.g. for drawing waveforms
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overview {
+	/// The edit count of the audio data this overview was made of
+	pub edit_count :u32,
+	/// The number of audio frames each overview sample represents
+	pub frames_per_overview_sample :u32,
+	/// The (min, max) overview samples, interleaved by channel
+	///
+	/// The number of channels is the `channels_per_frame` value
+	/// from the audio description.
+	pub samples :Vec<(i16, i16)>,
+}
+
+impl Overview {
+	/// Returns an iterator over the (min, max) samples of one channel
+	///
+	/// `channel_count` is the number of channels the samples are interleaved
+	/// by, as stored in the audio description.
+	/// Returns `None` if `channel` isn't smaller than `channel_count`.
+	pub fn samples_for_channel<'a>(&'a self, channel :u32, channel_count :u32)
+			-> Option<OverviewChannelSamples<'a>> {
+		if channel >= channel_count {
+			return None;
+		}
+		Some(OverviewChannelSamples {
+			samples : &self.samples,
+			idx : channel as usize,
+			step : channel_count as usize,
+		})
+	}
+}
+
+/// Iterator over the samples of a single channel of an `Overview`
+pub struct OverviewChannelSamples<'a> {
+	samples :&'a [(i16, i16)],
+	idx :usize,
+	step :usize,
+}
+
+impl<'a> Iterator for OverviewChannelSamples<'a> {
+	type Item = (i16, i16);
+	fn next(&mut self) -> Option<(i16, i16)> {
+		let res = self.samples.get(self.idx).map(|v| *v);
+		if res.is_some() {
+			self.idx += self.step;
+		}
+		res
+	}
+}
+
 /// Returns whether `decode_chunk` can decode chunks with the given type
 pub fn can_decode_chunk_type(chunk_type :ChunkType) -> bool {
 	use ChunkType::*;
@@ -104,7 +673,14 @@ pub fn can_decode_chunk_type(chunk_type :ChunkType) -> bool {
 		PacketTable |
 		ChannelLayout |
 		MagicCookie |
-		Info
+		Marker |
+		Region |
+		Strings |
+		Overview |
+		EditComments |
+		Info |
+		UniqueMaterialIdentifier |
+		UserDefined
 		=> true,
 		_ => false,
 	}
@@ -117,8 +693,8 @@ pub fn can_decode_chunk_type(chunk_type :ChunkType) -> bool {
 pub fn decode_chunk(chunk_type :ChunkType, mut chunk_content :Vec<u8>)
 		-> Result<CafChunk, CafError> {
 	use byteorder::BigEndian as Be;
-	use byteorder::ReadBytesExt;
-	use std::io::{Cursor, BufRead};
+	use io::ReadBytesExt;
+	use io::{Cursor, BufRead, Error as IoError, ErrorKind};
 	// ReaD with big endian order and Try
 	macro_rules! rdt {
 		($rdr:ident, $func:ident) => { try!($rdr.$func::<Be>()) }
@@ -126,11 +702,11 @@ pub fn decode_chunk(chunk_type :ChunkType, mut chunk_content :Vec<u8>)
 	match chunk_type {
 			ChunkType::AudioDescription => {
 				let mut rdr = Cursor::new(&chunk_content);
-				let sample_rate = rdr.read_f64::<Be>().unwrap();
+				let sample_rate = rdt!(rdr, read_f64);
 				Ok(CafChunk::Desc(AudioDescription {
 					sample_rate : sample_rate,
-					format_id : FormatType::from(rdr.read_u32::<Be>().unwrap()),
-					format_flags : rdr.read_u32::<Be>().unwrap(),
+					format_id : FormatType::from(rdt!(rdr, read_u32)),
+					format_flags : rdt!(rdr, read_u32),
 					bytes_per_packet : rdt!(rdr,read_u32),
 					frames_per_packet : rdt!(rdr,read_u32),
 					channels_per_frame : rdt!(rdr,read_u32),
@@ -140,28 +716,27 @@ pub fn decode_chunk(chunk_type :ChunkType, mut chunk_content :Vec<u8>)
 			ChunkType::AudioData => {
 				let edit_count = {
 					let mut rdr = Cursor::new(&chunk_content);
-					rdr.read_u32::<Be>().unwrap()
+					rdt!(rdr, read_u32)
 				};
 				// Remove the value just read from the vec
-				let new_chunk_content_len = chunk_content.len() - 4;
-				for i in 0..new_chunk_content_len {
-					chunk_content[i] = chunk_content[i + 4];
-				}
-				chunk_content.truncate(new_chunk_content_len);
-				Ok(CafChunk::AudioDataInMemory(
-					edit_count,
-					chunk_content
-				))
+				chunk_content.drain(..4);
+				Ok(CafChunk::AudioDataInMemory(AudioData::new(edit_count, chunk_content)))
 			},
 			ChunkType::PacketTable => {
 				let mut rdr = Cursor::new(&chunk_content);
 				let num_packets =  rdt!(rdr, read_i64);
+				if num_packets < 0 {
+					try!(Err(IoError::new(ErrorKind::InvalidData,
+						"negative packet count")));
+				}
 				Ok(CafChunk::PacketTable(PacketTable {
 					num_valid_frames : rdt!(rdr, read_i64),
 					num_priming_frames : rdt!(rdr, read_i32),
 					num_remainder_frames : rdt!(rdr, read_i32),
 					lengths : {
-						let mut lengths = Vec::with_capacity(num_packets as usize);
+						// Each length takes at least one byte
+						let mut lengths = Vec::with_capacity(
+							bounded_capacity(&rdr, num_packets as u64, 1));
 						for _ in 0..num_packets {
 							let b = try!(read_vlq(&mut rdr));
 							lengths.push(b);
@@ -172,10 +747,11 @@ pub fn decode_chunk(chunk_type :ChunkType, mut chunk_content :Vec<u8>)
 			},
 			ChunkType::ChannelLayout => {
 				let mut rdr = Cursor::new(&chunk_content);
-				let channel_layout_tag = rdr.read_u32::<Be>().unwrap();
-				let channel_bitmap = rdr.read_u32::<Be>().unwrap();
+				let channel_layout_tag = rdt!(rdr, read_u32);
+				let channel_bitmap = rdt!(rdr, read_u32);
 				let channel_descriptions_count = rdt!(rdr, read_u32);
-				let mut descs = Vec::with_capacity(channel_descriptions_count as usize);
+				let mut descs = Vec::with_capacity(bounded_capacity(&rdr,
+					channel_descriptions_count as u64, CHANNEL_DESCRIPTION_SIZE));
 				for _ in 0..channel_descriptions_count {
 					descs.push(ChannelDescription {
 						channel_label : rdt!(rdr, read_u32),
@@ -194,14 +770,101 @@ pub fn decode_chunk(chunk_type :ChunkType, mut chunk_content :Vec<u8>)
 				chunk_content
 			)),
 			// ...
-			ChunkType::Info => {
+			ChunkType::Marker => {
+				let mut rdr = Cursor::new(&chunk_content);
+				let smpte_time_type = rdt!(rdr, read_u32);
+				let num_markers = rdt!(rdr, read_u32);
+				let mut markers = Vec::with_capacity(
+					bounded_capacity(&rdr, num_markers as u64, MARKER_SIZE));
+				for _ in 0..num_markers {
+					markers.push(try!(read_marker(&mut rdr)));
+				}
+				Ok(CafChunk::Markers(Markers {
+					smpte_time_type : smpte_time_type,
+					markers : markers,
+				}))
+			},
+			ChunkType::Region => {
+				let mut rdr = Cursor::new(&chunk_content);
+				let smpte_time_type = rdt!(rdr, read_u32);
+				let num_regions = rdt!(rdr, read_u32);
+				// Each region takes at least 12 bytes
+				let mut regions = Vec::with_capacity(bounded_capacity(&rdr,
+					num_regions as u64, 12));
+				for _ in 0..num_regions {
+					let region_id = rdt!(rdr, read_u32);
+					let flags = RegionFlags(rdt!(rdr, read_u32));
+					let num_markers = rdt!(rdr, read_u32);
+					let mut markers = Vec::with_capacity(
+						bounded_capacity(&rdr, num_markers as u64, MARKER_SIZE));
+					for _ in 0..num_markers {
+						markers.push(try!(read_marker(&mut rdr)));
+					}
+					regions.push(CafRegion {
+						region_id : region_id,
+						flags : flags,
+						markers : markers,
+					});
+				}
+				Ok(CafChunk::Regions(Regions {
+					smpte_time_type : smpte_time_type,
+					regions : regions,
+				}))
+			},
+			ChunkType::Strings => {
+				let mut rdr = Cursor::new(&chunk_content);
+				let num_entries = rdt!(rdr, read_u32);
+				// Each ID takes 12 bytes
+				let mut ids = Vec::with_capacity(bounded_capacity(&rdr,
+					num_entries as u64, 12));
+				for _ in 0..num_entries {
+					ids.push((rdt!(rdr, read_u32), rdt!(rdr, read_i64)));
+				}
+				let strings = &chunk_content[rdr.position() as usize ..];
+				let mut res = Vec::with_capacity(ids.len());
+				for (id, offs) in ids {
+					if offs < 0 || offs as u64 > strings.len() as u64 {
+						try!(Err(IoError::new(ErrorKind::InvalidData,
+							"string offset out of bounds")));
+					}
+					let s = &strings[offs as usize ..];
+					// The strings are terminated by \0
+					let len = s.iter().position(|&b| b == 0).unwrap_or(s.len());
+					res.push((id, try!(String::from_utf8(s[..len].to_vec()))));
+				}
+				Ok(CafChunk::Strings(res))
+			},
+			// ...
+			ChunkType::Overview => {
+				let mut rdr = Cursor::new(&chunk_content);
+				let edit_count = rdt!(rdr, read_u32);
+				let frames_per_overview_sample = rdt!(rdr, read_u32);
+				let num_samples = chunk_content.len().saturating_sub(8) / 4;
+				let mut samples = Vec::with_capacity(num_samples);
+				for _ in 0..num_samples {
+					samples.push((rdt!(rdr, read_i16), rdt!(rdr, read_i16)));
+				}
+				Ok(CafChunk::Overview(Overview {
+					edit_count : edit_count,
+					frames_per_overview_sample : frames_per_overview_sample,
+					samples : samples,
+				}))
+			},
+			// ...
+			// The edit comments chunk has the same format as the info chunk
+			ChunkType::EditComments | ChunkType::Info => {
 				let mut rdr = Cursor::new(&chunk_content);
 				let num_entries = rdt!(rdr, read_u32);
-				let mut res = Vec::with_capacity(num_entries as usize);
+				// Each entry takes at least two bytes for the \0 terminators
+				let mut res = Vec::with_capacity(bounded_capacity(&rdr,
+					num_entries as u64, 2));
 				for _ in 0..num_entries {
 					let mut key = Vec::new();
 					let mut val = Vec::new();
-					try!(rdr.read_until(0, &mut key));
+					if try!(rdr.read_until(0, &mut key)) == 0 {
+						try!(Err(IoError::new(ErrorKind::UnexpectedEof,
+							"EOF inside of entry list")));
+					}
 					try!(rdr.read_until(0, &mut val));
 					// Remove the trailing \0. Somehow neither
 					// read_until nor from_utf8 does this for us.
@@ -209,24 +872,188 @@ pub fn decode_chunk(chunk_type :ChunkType, mut chunk_content :Vec<u8>)
 					val.pop();
 					res.push((try!(String::from_utf8(key)), try!(String::from_utf8(val))));
 				}
-				Ok(CafChunk::Info(res))
+				if chunk_type == ChunkType::EditComments {
+					Ok(CafChunk::EditComments(res))
+				} else {
+					Ok(CafChunk::Info(res))
+				}
+			},
+			ChunkType::UniqueMaterialIdentifier => {
+				if chunk_content.len() < 64 {
+					try!(Err(IoError::new(ErrorKind::InvalidData,
+						"UMID chunk shorter than 64 bytes")));
+				}
+				Ok(CafChunk::UniqueMaterialIdentifier(chunk_content))
+			},
+			ChunkType::UserDefined => {
+				let mut uuid = [0; 16];
+				{
+					let mut rdr = Cursor::new(&chunk_content);
+					try!(rdr.read_exact(&mut uuid));
+				}
+				// Remove the UUID from the vec, the rest is the payload
+				chunk_content.drain(..16);
+				Ok(CafChunk::UserDefined {
+					uuid : uuid,
+					data : chunk_content,
+				})
 			},
 			// ...
 			_ => try!(Err(CafError::UnsupportedChunkType(chunk_type))),
 	}
 }
 
-fn read_vlq<T :Read>(rdr :&mut T) -> Result<u64, IoError> {
-	let mut res = 0;
-	let mut buf = [0; 1];
-	// TODO ensure we don't exceed 64 bytes.
-	loop {
-		try!(rdr.read_exact(&mut buf));
-		let byte = buf[0];
-		res <<= 7;
-		res |= (byte & 127) as u64;
-		if byte & 128 == 0 {
-			return Ok(res);
-		}
+/// Capacity to reserve for `count` entries that are yet to be read
+///
+/// The count comes from the file, so it is bounded by the number of
+/// entries of at least `min_size` bytes that fit into the rest of the chunk.
+fn bounded_capacity(rdr :&::io::Cursor<&Vec<u8>>, count :u64, min_size :usize) -> usize {
+	let remaining = (rdr.get_ref().len() as u64).saturating_sub(rdr.position());
+	cmp::min(count, remaining / min_size as u64) as usize
+}
+
+/// Size of a channel description inside the channel layout chunk
+const CHANNEL_DESCRIPTION_SIZE :usize = 20;
+
+/// Size of a marker inside the marker and region chunks
+const MARKER_SIZE :usize = 28;
+
+fn read_marker<T :Read>(rdr :&mut T) -> Result<CafMarker, CafError> {
+	use byteorder::BigEndian as Be;
+	use io::ReadBytesExt;
+	Ok(CafMarker {
+		marker_type : try!(rdr.read_u32::<Be>()),
+		frame_position : try!(rdr.read_f64::<Be>()),
+		marker_id : try!(rdr.read_u32::<Be>()),
+		smpte_time : SmpteTime {
+			hours : try!(rdr.read_i8()),
+			minutes : try!(rdr.read_i8()),
+			seconds : try!(rdr.read_i8()),
+			frames : try!(rdr.read_i8()),
+			sub_frame_sample_offset : try!(rdr.read_u32::<Be>()),
+		},
+		channel : try!(rdr.read_u32::<Be>()),
+	})
+}
+
+fn write_marker(res :&mut Vec<u8>, marker :&CafMarker) -> Result<(), CafError> {
+	use byteorder::BigEndian as Be;
+	use io::WriteBytesExt;
+	try!(res.write_u32::<Be>(marker.marker_type));
+	try!(res.write_f64::<Be>(marker.frame_position));
+	try!(res.write_u32::<Be>(marker.marker_id));
+	try!(res.write_i8(marker.smpte_time.hours));
+	try!(res.write_i8(marker.smpte_time.minutes));
+	try!(res.write_i8(marker.smpte_time.seconds));
+	try!(res.write_i8(marker.smpte_time.frames));
+	try!(res.write_u32::<Be>(marker.smpte_time.sub_frame_sample_offset));
+	try!(res.write_u32::<Be>(marker.channel));
+	Ok(())
+}
+
+/// Encodes an in-memory chunk into its content bytes
+///
+/// This is the inverse of `decode_chunk`: the returned
+/// bytes don't include the chunk header.
+pub fn encode_chunk(chunk :&CafChunk) -> Result<Vec<u8>, CafError> {
+	use byteorder::BigEndian as Be;
+	use io::WriteBytesExt;
+	let mut res = Vec::new();
+	match chunk {
+		&CafChunk::Desc(ref desc) => {
+			try!(res.write_f64::<Be>(desc.sample_rate));
+			try!(res.write_u32::<Be>(desc.format_id.into()));
+			try!(res.write_u32::<Be>(desc.format_flags));
+			try!(res.write_u32::<Be>(desc.bytes_per_packet));
+			try!(res.write_u32::<Be>(desc.frames_per_packet));
+			try!(res.write_u32::<Be>(desc.channels_per_frame));
+			try!(res.write_u32::<Be>(desc.bits_per_channel));
+		},
+		&CafChunk::AudioDataInMemory(ref audio_data) => {
+			try!(res.write_u32::<Be>(audio_data.edit_count));
+			res.extend_from_slice(&audio_data.data);
+		},
+		&CafChunk::PacketTable(ref table) => {
+			try!(res.write_i64::<Be>(table.lengths.len() as i64));
+			try!(res.write_i64::<Be>(table.num_valid_frames));
+			try!(res.write_i32::<Be>(table.num_priming_frames));
+			try!(res.write_i32::<Be>(table.num_remainder_frames));
+			for &length in table.lengths.iter() {
+				write_vlq(&mut res, length);
+			}
+		},
+		&CafChunk::ChanLayout(ref layout) => {
+			try!(res.write_u32::<Be>(layout.channel_layout_tag));
+			try!(res.write_u32::<Be>(layout.channel_bitmap));
+			try!(res.write_u32::<Be>(layout.channel_descriptions.len() as u32));
+			for desc in layout.channel_descriptions.iter() {
+				try!(res.write_u32::<Be>(desc.channel_label));
+				try!(res.write_u32::<Be>(desc.channel_flags));
+				try!(res.write_f32::<Be>(desc.coordinates.0));
+				try!(res.write_f32::<Be>(desc.coordinates.1));
+				try!(res.write_f32::<Be>(desc.coordinates.2));
+			}
+		},
+		&CafChunk::MagicCookie(ref cookie) => {
+			res.extend_from_slice(cookie);
+		},
+		&CafChunk::Markers(ref markers) => {
+			try!(res.write_u32::<Be>(markers.smpte_time_type));
+			try!(res.write_u32::<Be>(markers.markers.len() as u32));
+			for marker in markers.markers.iter() {
+				try!(write_marker(&mut res, marker));
+			}
+		},
+		&CafChunk::Regions(ref regions) => {
+			try!(res.write_u32::<Be>(regions.smpte_time_type));
+			try!(res.write_u32::<Be>(regions.regions.len() as u32));
+			for region in regions.regions.iter() {
+				try!(res.write_u32::<Be>(region.region_id));
+				try!(res.write_u32::<Be>(region.flags.0));
+				try!(res.write_u32::<Be>(region.markers.len() as u32));
+				for marker in region.markers.iter() {
+					try!(write_marker(&mut res, marker));
+				}
+			}
+		},
+		&CafChunk::Overview(ref overview) => {
+			try!(res.write_u32::<Be>(overview.edit_count));
+			try!(res.write_u32::<Be>(overview.frames_per_overview_sample));
+			for &(min, max) in overview.samples.iter() {
+				try!(res.write_i16::<Be>(min));
+				try!(res.write_i16::<Be>(max));
+			}
+		},
+		&CafChunk::Strings(ref entries) => {
+			try!(res.write_u32::<Be>(entries.len() as u32));
+			let mut offs = 0;
+			for &(id, ref s) in entries.iter() {
+				try!(res.write_u32::<Be>(id));
+				try!(res.write_i64::<Be>(offs));
+				offs += s.len() as i64 + 1;
+			}
+			for &(_, ref s) in entries.iter() {
+				res.extend_from_slice(s.as_bytes());
+				res.push(0);
+			}
+		},
+		&CafChunk::EditComments(ref entries) |
+		&CafChunk::Info(ref entries) => {
+			try!(res.write_u32::<Be>(entries.len() as u32));
+			for &(ref key, ref val) in entries.iter() {
+				res.extend_from_slice(key.as_bytes());
+				res.push(0);
+				res.extend_from_slice(val.as_bytes());
+				res.push(0);
+			}
+		},
+		&CafChunk::UniqueMaterialIdentifier(ref umid) => {
+			res.extend_from_slice(umid);
+		},
+		&CafChunk::UserDefined { ref uuid, ref data } => {
+			res.extend_from_slice(uuid);
+			res.extend_from_slice(data);
+		},
 	}
+	Ok(res)
 }
@@ -13,10 +13,28 @@ In muliple places, the spec provides lists of IDs, saying
 that the list is non exhaustive.
 */
 
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+use core::fmt::{self, Display};
+use alloc::string::String;
+
+/// Writes the fourcc as its four characters, e.g. `free`
+///
+/// If any of the bytes isn't printable ASCII, the value is written in hex.
+fn fmt_fourcc(v :u32, f :&mut fmt::Formatter) -> Result<(), fmt::Error> {
+	let bytes = [(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8];
+	if bytes.iter().all(|&b| b >= 0x20 && b < 0x7f) {
+		let name :String = bytes.iter().map(|&b| b as char).collect();
+		write!(f, "{}", name)
+	} else {
+		write!(f, "0x{:08x}", v)
+	}
+}
+
 /// Module containing the different specified chunk types
 ///
 /// Beware, the spec explicitly says that its list is non exhaustive.
-mod chunk_types {
+pub mod chunk_types {
 	// The order is not random, its how it appears in the spec, bear this in mind.
 	// The spec says that this list is not exhaustive, so we can't use an enum here.
 	// Especially, users may add their own custom chunk types provided those are
@@ -27,7 +45,7 @@ mod chunk_types {
 	pub const PACKET_TABLE :u32 = 0x70_61_6b_74; // "pakt"
 	pub const CHANNEL_LAYOUT :u32 = 0x63_68_61_6e; // "chan"
 	pub const MAGIC_COOKIE :u32 = 0x6b_75_6b_69; // "kuki"
-	pub const STRINGS :u32 = 0x73_74_42_67; // "strg"
+	pub const STRINGS :u32 = 0x73_74_72_67; // "strg"
 	pub const MARKER :u32 = 0x6d_61_72_6b; // "mark"
 	pub const REGION :u32 = 0x72_65_67_6e; // "regn"
 	pub const INSTRUMENT :u32 = 0x69_6e_73_74; // "inst"
@@ -50,7 +68,8 @@ mod chunk_types {
 /// and that users may add their own unofficial chunk types
 /// from outside of the reserved range of chunks.
 /// Those chunk types are represented by the `Other` variant.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ChunkType {
 	/// mChunkType for the "Audio Description" chunk
 	AudioDescription,
@@ -121,10 +140,67 @@ impl From<u32> for ChunkType {
 	}
 }
 
+impl From<ChunkType> for u32 {
+	fn from(v :ChunkType) -> Self {
+		use self::chunk_types::*;
+		use self::ChunkType::*;
+		match v {
+			AudioDescription => AUDIO_DESCRIPTION,
+			AudioData => AUDIO_DATA,
+			PacketTable => PACKET_TABLE,
+			ChannelLayout => CHANNEL_LAYOUT,
+			MagicCookie => MAGIC_COOKIE,
+			Strings => STRINGS,
+			Marker => MARKER,
+			Region => REGION,
+			Instrument => INSTRUMENT,
+			Midi => MIDI,
+			Overview => OVERVIEW,
+			Peak => PEAK,
+			EditComments => EDIT_COMMENTS,
+			Info => INFO,
+			UniqueMaterialIdentifier => UNIQUE_MATERIAL_IDENTIFIER,
+			UserDefined => USER_DEFINED,
+			Free => FREE,
+			Other(v) => v,
+		}
+	}
+}
+
+impl Display for ChunkType {
+	/// Writes the name of the chunk type, e.g. `Audio Description`
+	///
+	/// For the `Other` variant, the fourcc is written instead.
+	fn fmt(&self, f :&mut fmt::Formatter) -> Result<(), fmt::Error> {
+		use self::ChunkType::*;
+		let name = match *self {
+			AudioDescription => "Audio Description",
+			AudioData => "Audio Data",
+			PacketTable => "Packet Table",
+			ChannelLayout => "Channel Layout",
+			MagicCookie => "Magic Cookie",
+			Strings => "Strings",
+			Marker => "Marker",
+			Region => "Region",
+			Instrument => "Instrument",
+			Midi => "MIDI",
+			Overview => "Overview",
+			Peak => "Peak",
+			EditComments => "Edit Comments",
+			Info => "Information",
+			UniqueMaterialIdentifier => "Unique Material Identifier",
+			UserDefined => "User-Defined",
+			Free => "Free",
+			Other(v) => return fmt_fourcc(v, f),
+		};
+		write!(f, "{}", name)
+	}
+}
+
 /// Module containing the different specified chunk types
 ///
 /// Beware, the spec explicitly says that its list is non exhaustive.
-mod format_types {
+pub mod format_types {
 	// The order is not random, its how it appears in the spec, bear this in mind.
 	// The spec says that this list is not exhaustive, so we can't use an enum here.
 
@@ -148,7 +224,8 @@ mod format_types {
 /// defined by the spec.
 ///
 /// The spec explicitly says that the list is not exhaustive.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FormatType {
 	/// mFormatID for Linear PCM
 	LinearPcm,
@@ -196,3 +273,541 @@ impl From<u32> for FormatType {
 		}
 	}
 }
+
+impl FormatType {
+	/// Returns the fourcc of the format, e.g. `b"lpcm"` for Linear PCM
+	pub fn fourcc_str(&self) -> [u8; 4] {
+		let v = u32::from(*self);
+		[(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]
+	}
+}
+
+impl From<FormatType> for u32 {
+	fn from(v :FormatType) -> Self {
+		use self::format_types::*;
+		use self::FormatType::*;
+		match v {
+			LinearPcm => LINEAR_PCM,
+			AppleIma4 => APPLE_IMA4,
+			Mpeg4Aac => MPEG4_AAC,
+			Mace3 => MACE3,
+			Mace6 => MACE6,
+			Ulaw => U_LAW,
+			Alaw => A_LAW,
+			MpegLayer1 => MPEG_LAYER_1,
+			MpegLayer2 => MPEG_LAYER_2,
+			MpegLayer3 => MPEG_LAYER_3,
+			AppleLossless => AAPL_LOSSLESS,
+			Other(v) => v,
+		}
+	}
+}
+
+impl Display for FormatType {
+	/// Writes the fourcc of the format, e.g. `lpcm`
+	fn fmt(&self, f :&mut fmt::Formatter) -> Result<(), fmt::Error> {
+		fmt_fourcc(u32::from(*self), f)
+	}
+}
+
+/// Module containing the channel layout tags
+///
+/// Beware, the list is non exhaustive.
+pub mod channel_layout_tags {
+	// The upper 16 bits identify the layout, the lower 16 bits
+	// give the number of channels.
+
+	pub const USE_CHANNEL_DESCRIPTIONS :u32 = 0x00_00_00_00; // (0 << 16) | 0
+	pub const USE_CHANNEL_BITMAP :u32 = 0x00_01_00_00; // (1 << 16) | 0
+	pub const MONO :u32 = 0x00_64_00_01; // (100 << 16) | 1
+	pub const STEREO :u32 = 0x00_65_00_02; // (101 << 16) | 2
+	pub const STEREO_HEADPHONES :u32 = 0x00_66_00_02; // (102 << 16) | 2
+	pub const MATRIX_STEREO :u32 = 0x00_67_00_02; // (103 << 16) | 2
+	pub const MID_SIDE :u32 = 0x00_68_00_02; // (104 << 16) | 2
+	pub const XY :u32 = 0x00_69_00_02; // (105 << 16) | 2
+	pub const BINAURAL :u32 = 0x00_6a_00_02; // (106 << 16) | 2
+	pub const AMBISONIC_B_FORMAT :u32 = 0x00_6b_00_04; // (107 << 16) | 4
+	pub const QUADRAPHONIC :u32 = 0x00_6c_00_04; // (108 << 16) | 4
+	pub const PENTAGONAL :u32 = 0x00_6d_00_05; // (109 << 16) | 5
+	pub const HEXAGONAL :u32 = 0x00_6e_00_06; // (110 << 16) | 6
+	pub const OCTAGONAL :u32 = 0x00_6f_00_08; // (111 << 16) | 8
+	pub const CUBE :u32 = 0x00_70_00_08; // (112 << 16) | 8
+	pub const MPEG_3_0_A :u32 = 0x00_71_00_03; // (113 << 16) | 3
+	pub const MPEG_3_0_B :u32 = 0x00_72_00_03; // (114 << 16) | 3
+	pub const MPEG_4_0_A :u32 = 0x00_73_00_04; // (115 << 16) | 4
+	pub const MPEG_4_0_B :u32 = 0x00_74_00_04; // (116 << 16) | 4
+	pub const MPEG_5_0_A :u32 = 0x00_75_00_05; // (117 << 16) | 5
+	pub const MPEG_5_0_B :u32 = 0x00_76_00_05; // (118 << 16) | 5
+	pub const MPEG_5_0_C :u32 = 0x00_77_00_05; // (119 << 16) | 5
+	pub const MPEG_5_0_D :u32 = 0x00_78_00_05; // (120 << 16) | 5
+	pub const MPEG_5_1_A :u32 = 0x00_79_00_06; // (121 << 16) | 6
+	pub const MPEG_5_1_B :u32 = 0x00_7a_00_06; // (122 << 16) | 6
+	pub const MPEG_5_1_C :u32 = 0x00_7b_00_06; // (123 << 16) | 6
+	pub const MPEG_5_1_D :u32 = 0x00_7c_00_06; // (124 << 16) | 6
+	pub const MPEG_6_1_A :u32 = 0x00_7d_00_07; // (125 << 16) | 7
+	pub const MPEG_7_1_A :u32 = 0x00_7e_00_08; // (126 << 16) | 8
+	pub const MPEG_7_1_B :u32 = 0x00_7f_00_08; // (127 << 16) | 8
+	pub const MPEG_7_1_C :u32 = 0x00_80_00_08; // (128 << 16) | 8
+	pub const EMAGIC_DEFAULT_7_1 :u32 = 0x00_81_00_08; // (129 << 16) | 8
+	pub const SMPTE_DTV :u32 = 0x00_82_00_08; // (130 << 16) | 8
+	pub const ITU_2_1 :u32 = 0x00_83_00_03; // (131 << 16) | 3
+	pub const ITU_2_2 :u32 = 0x00_84_00_04; // (132 << 16) | 4
+	pub const DISCRETE_IN_ORDER :u32 = 0x00_93_00_00; // (147 << 16) | channel count
+	pub const UNKNOWN :u32 = 0xff_ff_00_00; // (0xffff << 16) | channel count
+}
+
+/// Channel layout tags, for the `channel_layout_tag` field of a channel layout
+///
+/// Only the more common tags are covered, and the list
+/// is non exhaustive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelLayoutTag {
+	/// Channels are described by the channel descriptions
+	UseChannelDescriptions,
+	/// Channels are described by the channel bitmap
+	UseChannelBitmap,
+	/// A single channel
+	Mono,
+	/// Left and right
+	Stereo,
+	/// Left and right, for headphones
+	StereoHeadphones,
+	/// Matrix encoded stereo, e.g. Dolby Surround
+	MatrixStereo,
+	/// Mid and side
+	MidSide,
+	/// Coincident mic pair
+	Xy,
+	/// Left and right, recorded binaurally
+	Binaural,
+	/// W, X, Y, Z
+	AmbisonicBFormat,
+	/// Front left, front right, back left, back right
+	Quadraphonic,
+	/// Five channels, arranged as a pentagon
+	Pentagonal,
+	/// Six channels, arranged as a hexagon
+	Hexagonal,
+	/// Eight channels, arranged as an octagon
+	Octagonal,
+	/// Eight channels, arranged as a cube
+	Cube,
+	/// L R C
+	Mpeg30A,
+	/// C L R
+	Mpeg30B,
+	/// L R C Cs
+	Mpeg40A,
+	/// C L R Cs
+	Mpeg40B,
+	/// L R C Ls Rs
+	Mpeg50A,
+	/// L R Ls Rs C
+	Mpeg50B,
+	/// L C R Ls Rs
+	Mpeg50C,
+	/// C L R Ls Rs
+	Mpeg50D,
+	/// L R C LFE Ls Rs
+	Mpeg51A,
+	/// L R Ls Rs C LFE
+	Mpeg51B,
+	/// L C R Ls Rs LFE
+	Mpeg51C,
+	/// C L R Ls Rs LFE
+	Mpeg51D,
+	/// L R C LFE Ls Rs Cs
+	Mpeg61A,
+	/// L R C LFE Ls Rs Lc Rc
+	Mpeg71A,
+	/// C Lc Rc L R Ls Rs LFE
+	Mpeg71B,
+	/// L R C LFE Ls Rs Rls Rrs
+	Mpeg71C,
+	/// L R Ls Rs C LFE Lc Rc
+	EmagicDefault71,
+	/// L R C LFE Ls Rs Lt Rt
+	SmpteDtv,
+	/// L R Cs
+	Itu21,
+	/// L R Ls Rs
+	Itu22,
+	/// Discrete channels, without any particular meaning,
+	/// with the number of channels
+	DiscreteInOrder(u32),
+	/// Unknown layout, with the number of channels
+	Unknown(u32),
+	/// Variant for all tags that were not mentioned in this list.
+	Other(u32),
+}
+
+impl From<u32> for ChannelLayoutTag {
+	fn from(v :u32) -> Self {
+		use self::channel_layout_tags::*;
+		use self::ChannelLayoutTag::*;
+		match v {
+			USE_CHANNEL_DESCRIPTIONS => UseChannelDescriptions,
+			USE_CHANNEL_BITMAP => UseChannelBitmap,
+			MONO => Mono,
+			STEREO => Stereo,
+			STEREO_HEADPHONES => StereoHeadphones,
+			MATRIX_STEREO => MatrixStereo,
+			MID_SIDE => MidSide,
+			XY => Xy,
+			BINAURAL => Binaural,
+			AMBISONIC_B_FORMAT => AmbisonicBFormat,
+			QUADRAPHONIC => Quadraphonic,
+			PENTAGONAL => Pentagonal,
+			HEXAGONAL => Hexagonal,
+			OCTAGONAL => Octagonal,
+			CUBE => Cube,
+			MPEG_3_0_A => Mpeg30A,
+			MPEG_3_0_B => Mpeg30B,
+			MPEG_4_0_A => Mpeg40A,
+			MPEG_4_0_B => Mpeg40B,
+			MPEG_5_0_A => Mpeg50A,
+			MPEG_5_0_B => Mpeg50B,
+			MPEG_5_0_C => Mpeg50C,
+			MPEG_5_0_D => Mpeg50D,
+			MPEG_5_1_A => Mpeg51A,
+			MPEG_5_1_B => Mpeg51B,
+			MPEG_5_1_C => Mpeg51C,
+			MPEG_5_1_D => Mpeg51D,
+			MPEG_6_1_A => Mpeg61A,
+			MPEG_7_1_A => Mpeg71A,
+			MPEG_7_1_B => Mpeg71B,
+			MPEG_7_1_C => Mpeg71C,
+			EMAGIC_DEFAULT_7_1 => EmagicDefault71,
+			SMPTE_DTV => SmpteDtv,
+			ITU_2_1 => Itu21,
+			ITU_2_2 => Itu22,
+			_ if v & 0xffff_0000 == DISCRETE_IN_ORDER => DiscreteInOrder(v & 0xffff),
+			_ if v & 0xffff_0000 == UNKNOWN => Unknown(v & 0xffff),
+			_ => Other(v),
+		}
+	}
+}
+
+impl From<ChannelLayoutTag> for u32 {
+	fn from(v :ChannelLayoutTag) -> Self {
+		use self::channel_layout_tags::*;
+		use self::ChannelLayoutTag::*;
+		match v {
+			UseChannelDescriptions => USE_CHANNEL_DESCRIPTIONS,
+			UseChannelBitmap => USE_CHANNEL_BITMAP,
+			Mono => MONO,
+			Stereo => STEREO,
+			StereoHeadphones => STEREO_HEADPHONES,
+			MatrixStereo => MATRIX_STEREO,
+			MidSide => MID_SIDE,
+			Xy => XY,
+			Binaural => BINAURAL,
+			AmbisonicBFormat => AMBISONIC_B_FORMAT,
+			Quadraphonic => QUADRAPHONIC,
+			Pentagonal => PENTAGONAL,
+			Hexagonal => HEXAGONAL,
+			Octagonal => OCTAGONAL,
+			Cube => CUBE,
+			Mpeg30A => MPEG_3_0_A,
+			Mpeg30B => MPEG_3_0_B,
+			Mpeg40A => MPEG_4_0_A,
+			Mpeg40B => MPEG_4_0_B,
+			Mpeg50A => MPEG_5_0_A,
+			Mpeg50B => MPEG_5_0_B,
+			Mpeg50C => MPEG_5_0_C,
+			Mpeg50D => MPEG_5_0_D,
+			Mpeg51A => MPEG_5_1_A,
+			Mpeg51B => MPEG_5_1_B,
+			Mpeg51C => MPEG_5_1_C,
+			Mpeg51D => MPEG_5_1_D,
+			Mpeg61A => MPEG_6_1_A,
+			Mpeg71A => MPEG_7_1_A,
+			Mpeg71B => MPEG_7_1_B,
+			Mpeg71C => MPEG_7_1_C,
+			EmagicDefault71 => EMAGIC_DEFAULT_7_1,
+			SmpteDtv => SMPTE_DTV,
+			Itu21 => ITU_2_1,
+			Itu22 => ITU_2_2,
+			DiscreteInOrder(n) => DISCRETE_IN_ORDER | (n & 0xffff),
+			Unknown(n) => UNKNOWN | (n & 0xffff),
+			Other(v) => v,
+		}
+	}
+}
+
+/// Module containing the channel labels
+///
+/// Beware, the list is non exhaustive.
+pub mod channel_labels {
+	pub const UNUSED :u32 = 0;
+	pub const LEFT :u32 = 1;
+	pub const RIGHT :u32 = 2;
+	pub const CENTER :u32 = 3;
+	pub const LFE_SCREEN :u32 = 4;
+	pub const LEFT_SURROUND :u32 = 5;
+	pub const RIGHT_SURROUND :u32 = 6;
+	pub const LEFT_CENTER :u32 = 7;
+	pub const RIGHT_CENTER :u32 = 8;
+	pub const CENTER_SURROUND :u32 = 9;
+	pub const LEFT_SURROUND_DIRECT :u32 = 10;
+	pub const RIGHT_SURROUND_DIRECT :u32 = 11;
+	pub const TOP_CENTER_SURROUND :u32 = 12;
+	pub const VERTICAL_HEIGHT_LEFT :u32 = 13;
+	pub const VERTICAL_HEIGHT_CENTER :u32 = 14;
+	pub const VERTICAL_HEIGHT_RIGHT :u32 = 15;
+	pub const TOP_BACK_LEFT :u32 = 16;
+	pub const TOP_BACK_CENTER :u32 = 17;
+	pub const TOP_BACK_RIGHT :u32 = 18;
+	pub const REAR_SURROUND_LEFT :u32 = 33;
+	pub const REAR_SURROUND_RIGHT :u32 = 34;
+	pub const LEFT_WIDE :u32 = 35;
+	pub const RIGHT_WIDE :u32 = 36;
+	pub const LFE2 :u32 = 37;
+	pub const LEFT_TOTAL :u32 = 38;
+	pub const RIGHT_TOTAL :u32 = 39;
+	pub const HEARING_IMPAIRED :u32 = 40;
+	pub const NARRATION :u32 = 41;
+	pub const MONO :u32 = 42;
+	pub const DIALOG_CENTRIC_MIX :u32 = 43;
+	pub const CENTER_SURROUND_DIRECT :u32 = 44;
+	pub const HAPTIC :u32 = 45;
+	pub const USE_COORDINATES :u32 = 100;
+	pub const AMBISONIC_W :u32 = 200;
+	pub const AMBISONIC_X :u32 = 201;
+	pub const AMBISONIC_Y :u32 = 202;
+	pub const AMBISONIC_Z :u32 = 203;
+	pub const MS_MID :u32 = 204;
+	pub const MS_SIDE :u32 = 205;
+	pub const XY_X :u32 = 206;
+	pub const XY_Y :u32 = 207;
+	pub const HEADPHONES_LEFT :u32 = 301;
+	pub const HEADPHONES_RIGHT :u32 = 302;
+	pub const CLICK_TRACK :u32 = 304;
+	pub const FOREIGN_LANGUAGE :u32 = 305;
+	pub const DISCRETE :u32 = 400;
+	/// Numbered discrete channels are given by `DISCRETE_0 | n`
+	pub const DISCRETE_0 :u32 = 0x00_01_00_00; // (1 << 16) | 0
+	pub const UNKNOWN :u32 = 0xff_ff_ff_ff;
+}
+
+/// Channel labels, for the `channel_label` field of a channel description
+///
+/// Each label names the speaker position a channel is meant for.
+/// The list is non exhaustive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelLabel {
+	/// The label is unknown
+	Unknown,
+	/// Unused channel
+	Unused,
+	/// Left
+	Left,
+	/// Right
+	Right,
+	/// Center
+	Center,
+	/// Low frequency effects
+	LfeScreen,
+	/// Left surround, behind the listener in 5.1
+	LeftSurround,
+	/// Right surround, behind the listener in 5.1
+	RightSurround,
+	/// Left center
+	LeftCenter,
+	/// Right center
+	RightCenter,
+	/// Center surround, behind the listener
+	CenterSurround,
+	/// Left surround direct, to the side of the listener
+	LeftSurroundDirect,
+	/// Right surround direct, to the side of the listener
+	RightSurroundDirect,
+	/// Top center surround
+	TopCenterSurround,
+	/// Vertical height left
+	VerticalHeightLeft,
+	/// Vertical height center
+	VerticalHeightCenter,
+	/// Vertical height right
+	VerticalHeightRight,
+	/// Top back left
+	TopBackLeft,
+	/// Top back center
+	TopBackCenter,
+	/// Top back right
+	TopBackRight,
+	/// Rear surround left
+	RearSurroundLeft,
+	/// Rear surround right
+	RearSurroundRight,
+	/// Left wide
+	LeftWide,
+	/// Right wide
+	RightWide,
+	/// Second low frequency effects channel
+	Lfe2,
+	/// Left channel of matrix encoded stereo
+	LeftTotal,
+	/// Right channel of matrix encoded stereo
+	RightTotal,
+	/// Hearing impaired mix
+	HearingImpaired,
+	/// Narration
+	Narration,
+	/// Mono
+	Mono,
+	/// Dialog centric mix
+	DialogCentricMix,
+	/// Center surround direct
+	CenterSurroundDirect,
+	/// Haptic feedback
+	Haptic,
+	/// The position is given by the coordinates of the description
+	UseCoordinates,
+	/// Ambisonic W
+	AmbisonicW,
+	/// Ambisonic X
+	AmbisonicX,
+	/// Ambisonic Y
+	AmbisonicY,
+	/// Ambisonic Z
+	AmbisonicZ,
+	/// Mid of a mid/side recording
+	MsMid,
+	/// Side of a mid/side recording
+	MsSide,
+	/// X of a coincident mic pair
+	XyX,
+	/// Y of a coincident mic pair
+	XyY,
+	/// Left headphone
+	HeadphonesLeft,
+	/// Right headphone
+	HeadphonesRight,
+	/// Click track
+	ClickTrack,
+	/// Foreign language
+	ForeignLanguage,
+	/// Discrete channel, without any particular meaning
+	Discrete,
+	/// Numbered discrete channel, without any particular meaning
+	DiscreteNumbered(u16),
+	/// Variant for all labels that were not mentioned in this list.
+	Other(u32),
+}
+
+impl From<u32> for ChannelLabel {
+	fn from(v :u32) -> Self {
+		use self::channel_labels::*;
+		use self::ChannelLabel::*;
+		match v {
+			UNKNOWN => Unknown,
+			UNUSED => Unused,
+			LEFT => Left,
+			RIGHT => Right,
+			CENTER => Center,
+			LFE_SCREEN => LfeScreen,
+			LEFT_SURROUND => LeftSurround,
+			RIGHT_SURROUND => RightSurround,
+			LEFT_CENTER => LeftCenter,
+			RIGHT_CENTER => RightCenter,
+			CENTER_SURROUND => CenterSurround,
+			LEFT_SURROUND_DIRECT => LeftSurroundDirect,
+			RIGHT_SURROUND_DIRECT => RightSurroundDirect,
+			TOP_CENTER_SURROUND => TopCenterSurround,
+			VERTICAL_HEIGHT_LEFT => VerticalHeightLeft,
+			VERTICAL_HEIGHT_CENTER => VerticalHeightCenter,
+			VERTICAL_HEIGHT_RIGHT => VerticalHeightRight,
+			TOP_BACK_LEFT => TopBackLeft,
+			TOP_BACK_CENTER => TopBackCenter,
+			TOP_BACK_RIGHT => TopBackRight,
+			REAR_SURROUND_LEFT => RearSurroundLeft,
+			REAR_SURROUND_RIGHT => RearSurroundRight,
+			LEFT_WIDE => LeftWide,
+			RIGHT_WIDE => RightWide,
+			LFE2 => Lfe2,
+			LEFT_TOTAL => LeftTotal,
+			RIGHT_TOTAL => RightTotal,
+			HEARING_IMPAIRED => HearingImpaired,
+			NARRATION => Narration,
+			MONO => Mono,
+			DIALOG_CENTRIC_MIX => DialogCentricMix,
+			CENTER_SURROUND_DIRECT => CenterSurroundDirect,
+			HAPTIC => Haptic,
+			USE_COORDINATES => UseCoordinates,
+			AMBISONIC_W => AmbisonicW,
+			AMBISONIC_X => AmbisonicX,
+			AMBISONIC_Y => AmbisonicY,
+			AMBISONIC_Z => AmbisonicZ,
+			MS_MID => MsMid,
+			MS_SIDE => MsSide,
+			XY_X => XyX,
+			XY_Y => XyY,
+			HEADPHONES_LEFT => HeadphonesLeft,
+			HEADPHONES_RIGHT => HeadphonesRight,
+			CLICK_TRACK => ClickTrack,
+			FOREIGN_LANGUAGE => ForeignLanguage,
+			DISCRETE => Discrete,
+			_ if v & 0xffff_0000 == DISCRETE_0 => DiscreteNumbered(v as u16),
+			_ => Other(v),
+		}
+	}
+}
+
+impl From<ChannelLabel> for u32 {
+	fn from(v :ChannelLabel) -> Self {
+		use self::channel_labels::*;
+		use self::ChannelLabel::*;
+		match v {
+			Unknown => UNKNOWN,
+			Unused => UNUSED,
+			Left => LEFT,
+			Right => RIGHT,
+			Center => CENTER,
+			LfeScreen => LFE_SCREEN,
+			LeftSurround => LEFT_SURROUND,
+			RightSurround => RIGHT_SURROUND,
+			LeftCenter => LEFT_CENTER,
+			RightCenter => RIGHT_CENTER,
+			CenterSurround => CENTER_SURROUND,
+			LeftSurroundDirect => LEFT_SURROUND_DIRECT,
+			RightSurroundDirect => RIGHT_SURROUND_DIRECT,
+			TopCenterSurround => TOP_CENTER_SURROUND,
+			VerticalHeightLeft => VERTICAL_HEIGHT_LEFT,
+			VerticalHeightCenter => VERTICAL_HEIGHT_CENTER,
+			VerticalHeightRight => VERTICAL_HEIGHT_RIGHT,
+			TopBackLeft => TOP_BACK_LEFT,
+			TopBackCenter => TOP_BACK_CENTER,
+			TopBackRight => TOP_BACK_RIGHT,
+			RearSurroundLeft => REAR_SURROUND_LEFT,
+			RearSurroundRight => REAR_SURROUND_RIGHT,
+			LeftWide => LEFT_WIDE,
+			RightWide => RIGHT_WIDE,
+			Lfe2 => LFE2,
+			LeftTotal => LEFT_TOTAL,
+			RightTotal => RIGHT_TOTAL,
+			HearingImpaired => HEARING_IMPAIRED,
+			Narration => NARRATION,
+			Mono => MONO,
+			DialogCentricMix => DIALOG_CENTRIC_MIX,
+			CenterSurroundDirect => CENTER_SURROUND_DIRECT,
+			Haptic => HAPTIC,
+			UseCoordinates => USE_COORDINATES,
+			AmbisonicW => AMBISONIC_W,
+			AmbisonicX => AMBISONIC_X,
+			AmbisonicY => AMBISONIC_Y,
+			AmbisonicZ => AMBISONIC_Z,
+			MsMid => MS_MID,
+			MsSide => MS_SIDE,
+			XyX => XY_X,
+			XyY => XY_Y,
+			HeadphonesLeft => HEADPHONES_LEFT,
+			HeadphonesRight => HEADPHONES_RIGHT,
+			ClickTrack => CLICK_TRACK,
+			ForeignLanguage => FOREIGN_LANGUAGE,
+			Discrete => DISCRETE,
+			DiscreteNumbered(n) => DISCRETE_0 | n as u32,
+			Other(v) => v,
+		}
+	}
+}
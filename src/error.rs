@@ -6,11 +6,13 @@
 // at your option. Please see the LICENSE file
 // attached to this source distribution for details.
 
-use std::string::FromUtf8Error;
-use std::io::{Error as IoError};
+use alloc::string::FromUtf8Error;
+use io::{Error as IoError};
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt::Display;
+use core::fmt::{self, Display};
 use ::ChunkType;
+use ::FormatType;
 
 #[derive(Debug)]
 pub enum CafError {
@@ -20,6 +22,23 @@ pub enum CafError {
 	NotCaf,
 	/// If the chunk can't be decoded because its type is not supported
 	UnsupportedChunkType(ChunkType),
+	/// If the audio can't be decoded to PCM samples by this crate
+	///
+	/// Compressed formats need to be decoded from the
+	/// packets, using a decoder for the format.
+	UnsupportedFormat(FormatType),
+	/// If a chunk with the given type was needed but couldn't be found
+	MissingChunk(ChunkType),
+	/// If a chunk can't be replaced in place because the
+	/// new content has a different size than the old one
+	ChunkSizeChanged,
+	/// If an operation needs the size of a chunk,
+	/// but the chunk has unspecified (-1) size
+	UnspecifiedChunkSize,
+	/// If a chunk header has a negative size other than -1
+	InvalidChunkSize(i64),
+	/// If the audio description is inconsistent, with the reason
+	InvalidAudioDescription(&'static str),
 }
 
 impl From<IoError> for CafError {
@@ -34,16 +53,29 @@ impl From<FromUtf8Error> for CafError {
 	}
 }
 
-impl Error for CafError {
-	fn description(&self) -> &str {
+impl CafError {
+	fn message(&self) -> &str {
 		use CafError::*;
 		match self {
 			&Io(_) => "IO error",
 			&FromUtf8(_) => "Can't decode UTF-8",
 			&NotCaf => "The given stream doesn't start with a CAF header",
 			&UnsupportedChunkType(_) => "Encountered a chunk with an unsupported type",
+			&UnsupportedFormat(_) => "The audio format can't be decoded to PCM, use the packet API instead",
+			&MissingChunk(_) => "A required chunk couldn't be found",
+			&ChunkSizeChanged => "The new chunk content has a different size than the old one",
+			&UnspecifiedChunkSize => "The chunk size is unspecified",
+			&InvalidChunkSize(_) => "Encountered a chunk with a negative size",
+			&InvalidAudioDescription(reason) => reason,
 		}
 	}
+}
+
+#[cfg(feature = "std")]
+impl Error for CafError {
+	fn description(&self) -> &str {
+		self.message()
+	}
 
 	fn cause(&self) -> Option<&Error> {
 		use CafError::*;
@@ -56,13 +88,19 @@ impl Error for CafError {
 }
 
 impl Display for CafError {
-	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> Result<(), ::std::fmt::Error> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
 		use CafError::*;
 		match *self {
 			Io(ref err) => err.fmt(f),
 			FromUtf8(ref err) => err.fmt(f),
+			MissingChunk(ch_type) => write!(f, "{}: {}", self.message(), ch_type),
+			InvalidChunkSize(size) => write!(f, "{}: {}", self.message(), size),
 			UnsupportedChunkType(_) |
-			NotCaf => write!(f, "{}", self.description()),
+			UnsupportedFormat(_) |
+			ChunkSizeChanged |
+			UnspecifiedChunkSize |
+			InvalidAudioDescription(_) |
+			NotCaf => write!(f, "{}", self.message()),
 		}
 	}
 }
@@ -0,0 +1,452 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+/*!
+The IO traits and types the readers and writers are generic over
+
+With the `std` feature (enabled by default), these are re-exports
+from `std::io`, so any `std::io::Read + Seek` type can be used.
+
+Without it, minimal replacements with the same names and signatures
+are provided, so that the crate only needs `alloc`.
+To decode from memory, wrap a byte slice or `Vec<u8>` into a `Cursor`,
+or implement `Read` and `Seek` for your own source.
+*/
+
+#[cfg(feature = "std")]
+pub use std::io::{Read, BufRead, Write, Seek, SeekFrom, Cursor, Take,
+	Error, ErrorKind, Result, copy, sink};
+#[cfg(feature = "std")]
+pub use byteorder::{ReadBytesExt, WriteBytesExt};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std::*;
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+	use core::{cmp, fmt};
+	use alloc::vec::Vec;
+	use byteorder::ByteOrder;
+
+	/// The kind of an IO error, a subset of the one in `std::io`
+	#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+	pub enum ErrorKind {
+		InvalidInput,
+		InvalidData,
+		UnexpectedEof,
+		WriteZero,
+		Interrupted,
+		Other,
+	}
+
+	impl ErrorKind {
+		fn as_str(&self) -> &'static str {
+			match *self {
+				ErrorKind::InvalidInput => "invalid input parameter",
+				ErrorKind::InvalidData => "invalid data",
+				ErrorKind::UnexpectedEof => "unexpected end of file",
+				ErrorKind::WriteZero => "write zero",
+				ErrorKind::Interrupted => "operation interrupted",
+				ErrorKind::Other => "other error",
+			}
+		}
+	}
+
+	/// An IO error, consisting of its kind and a description
+	#[derive(Debug)]
+	pub struct Error {
+		kind :ErrorKind,
+		msg :&'static str,
+	}
+
+	impl Error {
+		pub fn new(kind :ErrorKind, msg :&'static str) -> Self {
+			Error {
+				kind : kind,
+				msg : msg,
+			}
+		}
+		pub fn kind(&self) -> ErrorKind {
+			self.kind
+		}
+	}
+
+	impl From<ErrorKind> for Error {
+		fn from(kind :ErrorKind) -> Self {
+			Error::new(kind, kind.as_str())
+		}
+	}
+
+	impl fmt::Display for Error {
+		fn fmt(&self, f :&mut fmt::Formatter) -> fmt::Result {
+			write!(f, "{}", self.msg)
+		}
+	}
+
+	pub type Result<T> = ::core::result::Result<T, Error>;
+
+	pub trait Read {
+		fn read(&mut self, buf :&mut [u8]) -> Result<usize>;
+		fn read_exact(&mut self, mut buf :&mut [u8]) -> Result<()> {
+			while buf.len() > 0 {
+				match self.read(buf) {
+					Ok(0) => break,
+					Ok(n) => {
+						let tmp = buf;
+						buf = &mut tmp[n..];
+					},
+					Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+					Err(e) => return Err(e),
+				}
+			}
+			if buf.len() > 0 {
+				return Err(Error::new(ErrorKind::UnexpectedEof,
+					"failed to fill whole buffer"));
+			}
+			Ok(())
+		}
+		fn read_to_end(&mut self, buf :&mut Vec<u8>) -> Result<usize> {
+			let start_len = buf.len();
+			let mut chunk = [0; 512];
+			loop {
+				match self.read(&mut chunk) {
+					Ok(0) => return Ok(buf.len() - start_len),
+					Ok(n) => buf.extend_from_slice(&chunk[..n]),
+					Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+					Err(e) => return Err(e),
+				}
+			}
+		}
+		fn by_ref(&mut self) -> &mut Self where Self :Sized {
+			self
+		}
+		fn take(self, limit :u64) -> Take<Self> where Self :Sized {
+			Take {
+				inner : self,
+				limit : limit,
+			}
+		}
+	}
+
+	impl<'a, R :Read + ?Sized> Read for &'a mut R {
+		fn read(&mut self, buf :&mut [u8]) -> Result<usize> {
+			(**self).read(buf)
+		}
+	}
+
+	impl<'a> Read for &'a [u8] {
+		fn read(&mut self, buf :&mut [u8]) -> Result<usize> {
+			let n = cmp::min(buf.len(), self.len());
+			let (a, b) = self.split_at(n);
+			buf[..n].copy_from_slice(a);
+			*self = b;
+			Ok(n)
+		}
+	}
+
+	pub trait BufRead :Read {
+		fn fill_buf(&mut self) -> Result<&[u8]>;
+		fn consume(&mut self, amt :usize);
+		fn read_until(&mut self, byte :u8, buf :&mut Vec<u8>) -> Result<usize> {
+			let mut read = 0;
+			loop {
+				let (done, used) = {
+					let available = try!(self.fill_buf());
+					match available.iter().position(|b| *b == byte) {
+						Some(i) => {
+							buf.extend_from_slice(&available[..i + 1]);
+							(true, i + 1)
+						},
+						None => {
+							buf.extend_from_slice(available);
+							(available.len() == 0, available.len())
+						},
+					}
+				};
+				self.consume(used);
+				read += used;
+				if done {
+					return Ok(read);
+				}
+			}
+		}
+	}
+
+	pub trait Write {
+		fn write(&mut self, buf :&[u8]) -> Result<usize>;
+		fn flush(&mut self) -> Result<()>;
+		fn write_all(&mut self, mut buf :&[u8]) -> Result<()> {
+			while buf.len() > 0 {
+				match self.write(buf) {
+					Ok(0) => return Err(Error::new(ErrorKind::WriteZero,
+						"failed to write whole buffer")),
+					Ok(n) => buf = &buf[n..],
+					Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+					Err(e) => return Err(e),
+				}
+			}
+			Ok(())
+		}
+	}
+
+	impl<'a, W :Write + ?Sized> Write for &'a mut W {
+		fn write(&mut self, buf :&[u8]) -> Result<usize> {
+			(**self).write(buf)
+		}
+		fn flush(&mut self) -> Result<()> {
+			(**self).flush()
+		}
+	}
+
+	impl Write for Vec<u8> {
+		fn write(&mut self, buf :&[u8]) -> Result<usize> {
+			self.extend_from_slice(buf);
+			Ok(buf.len())
+		}
+		fn flush(&mut self) -> Result<()> {
+			Ok(())
+		}
+	}
+
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub enum SeekFrom {
+		Start(u64),
+		End(i64),
+		Current(i64),
+	}
+
+	pub trait Seek {
+		fn seek(&mut self, pos :SeekFrom) -> Result<u64>;
+	}
+
+	impl<'a, S :Seek + ?Sized> Seek for &'a mut S {
+		fn seek(&mut self, pos :SeekFrom) -> Result<u64> {
+			(**self).seek(pos)
+		}
+	}
+
+	/// Reader adapter limiting the number of bytes read
+	#[derive(Debug)]
+	pub struct Take<R> {
+		inner :R,
+		limit :u64,
+	}
+
+	impl<R> Take<R> {
+		pub fn limit(&self) -> u64 {
+			self.limit
+		}
+		pub fn into_inner(self) -> R {
+			self.inner
+		}
+	}
+
+	impl<R :Read> Read for Take<R> {
+		fn read(&mut self, buf :&mut [u8]) -> Result<usize> {
+			if self.limit == 0 {
+				return Ok(0);
+			}
+			let max = cmp::min(buf.len() as u64, self.limit) as usize;
+			let n = try!(self.inner.read(&mut buf[..max]));
+			self.limit -= n as u64;
+			Ok(n)
+		}
+	}
+
+	/// In-memory reader and writer, keeping track of the position
+	#[derive(Debug, Clone, Default)]
+	pub struct Cursor<T> {
+		inner :T,
+		pos :u64,
+	}
+
+	impl<T> Cursor<T> {
+		pub fn new(inner :T) -> Self {
+			Cursor {
+				inner : inner,
+				pos : 0,
+			}
+		}
+		pub fn into_inner(self) -> T {
+			self.inner
+		}
+		pub fn get_ref(&self) -> &T {
+			&self.inner
+		}
+		pub fn get_mut(&mut self) -> &mut T {
+			&mut self.inner
+		}
+		pub fn position(&self) -> u64 {
+			self.pos
+		}
+		pub fn set_position(&mut self, pos :u64) {
+			self.pos = pos;
+		}
+	}
+
+	impl<T> Read for Cursor<T> where T :AsRef<[u8]> {
+		fn read(&mut self, buf :&mut [u8]) -> Result<usize> {
+			let n = try!(Read::read(&mut try!(self.fill_buf()), buf));
+			self.pos += n as u64;
+			Ok(n)
+		}
+	}
+
+	impl<T> BufRead for Cursor<T> where T :AsRef<[u8]> {
+		fn fill_buf(&mut self) -> Result<&[u8]> {
+			let data = self.inner.as_ref();
+			let start = cmp::min(self.pos, data.len() as u64) as usize;
+			Ok(&data[start..])
+		}
+		fn consume(&mut self, amt :usize) {
+			self.pos += amt as u64;
+		}
+	}
+
+	impl<T> Seek for Cursor<T> where T :AsRef<[u8]> {
+		fn seek(&mut self, pos :SeekFrom) -> Result<u64> {
+			let (base, offs) = match pos {
+				SeekFrom::Start(v) => {
+					self.pos = v;
+					return Ok(v);
+				},
+				SeekFrom::End(v) => (self.inner.as_ref().len() as u64, v),
+				SeekFrom::Current(v) => (self.pos, v),
+			};
+			let new_pos = if offs >= 0 {
+				base.checked_add(offs as u64)
+			} else {
+				base.checked_sub(offs.wrapping_neg() as u64)
+			};
+			match new_pos {
+				Some(v) => {
+					self.pos = v;
+					Ok(v)
+				},
+				None => Err(Error::new(ErrorKind::InvalidInput,
+					"invalid seek to a negative or overflowing position")),
+			}
+		}
+	}
+
+	impl Write for Cursor<Vec<u8>> {
+		fn write(&mut self, buf :&[u8]) -> Result<usize> {
+			let pos = self.pos as usize;
+			let vec = &mut self.inner;
+			if vec.len() < pos {
+				vec.resize(pos, 0);
+			}
+			let overlap = cmp::min(vec.len() - pos, buf.len());
+			vec[pos..pos + overlap].copy_from_slice(&buf[..overlap]);
+			vec.extend_from_slice(&buf[overlap..]);
+			self.pos += buf.len() as u64;
+			Ok(buf.len())
+		}
+		fn flush(&mut self) -> Result<()> {
+			Ok(())
+		}
+	}
+
+	/// Writer that discards all data, returned by `sink`
+	#[derive(Debug)]
+	pub struct Sink {
+		_priv :(),
+	}
+
+	pub fn sink() -> Sink {
+		Sink { _priv : () }
+	}
+
+	impl Write for Sink {
+		fn write(&mut self, buf :&[u8]) -> Result<usize> {
+			Ok(buf.len())
+		}
+		fn flush(&mut self) -> Result<()> {
+			Ok(())
+		}
+	}
+
+	/// Copies the whole content of the reader to the writer
+	pub fn copy<R :?Sized, W :?Sized>(rdr :&mut R, wtr :&mut W) -> Result<u64>
+			where R :Read, W :Write {
+		let mut buf = [0; 512];
+		let mut written = 0;
+		loop {
+			let n = match rdr.read(&mut buf) {
+				Ok(0) => return Ok(written),
+				Ok(n) => n,
+				Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+				Err(e) => return Err(e),
+			};
+			try!(wtr.write_all(&buf[..n]));
+			written += n as u64;
+		}
+	}
+
+	macro_rules! read_fn {
+		($name:ident, $ty:ty, $len:expr, $conv:ident) => {
+			fn $name<B :ByteOrder>(&mut self) -> Result<$ty> {
+				let mut buf = [0; $len];
+				try!(self.read_exact(&mut buf));
+				Ok(B::$conv(&buf))
+			}
+		}
+	}
+
+	/// Reading of numbers, like the trait of the same name in `byteorder`
+	pub trait ReadBytesExt :Read {
+		fn read_u8(&mut self) -> Result<u8> {
+			let mut buf = [0; 1];
+			try!(self.read_exact(&mut buf));
+			Ok(buf[0])
+		}
+		fn read_i8(&mut self) -> Result<i8> {
+			Ok(try!(self.read_u8()) as i8)
+		}
+		read_fn!(read_u16, u16, 2, read_u16);
+		read_fn!(read_i16, i16, 2, read_i16);
+		read_fn!(read_u32, u32, 4, read_u32);
+		read_fn!(read_i32, i32, 4, read_i32);
+		read_fn!(read_u64, u64, 8, read_u64);
+		read_fn!(read_i64, i64, 8, read_i64);
+		read_fn!(read_f32, f32, 4, read_f32);
+		read_fn!(read_f64, f64, 8, read_f64);
+	}
+
+	impl<R :Read + ?Sized> ReadBytesExt for R {}
+
+	macro_rules! write_fn {
+		($name:ident, $ty:ty, $len:expr, $conv:ident) => {
+			fn $name<B :ByteOrder>(&mut self, v :$ty) -> Result<()> {
+				let mut buf = [0; $len];
+				B::$conv(&mut buf, v);
+				self.write_all(&buf)
+			}
+		}
+	}
+
+	/// Writing of numbers, like the trait of the same name in `byteorder`
+	pub trait WriteBytesExt :Write {
+		fn write_u8(&mut self, v :u8) -> Result<()> {
+			self.write_all(&[v])
+		}
+		fn write_i8(&mut self, v :i8) -> Result<()> {
+			self.write_all(&[v as u8])
+		}
+		write_fn!(write_u16, u16, 2, write_u16);
+		write_fn!(write_i16, i16, 2, write_i16);
+		write_fn!(write_u32, u32, 4, write_u32);
+		write_fn!(write_i32, i32, 4, write_i32);
+		write_fn!(write_u64, u64, 8, write_u64);
+		write_fn!(write_i64, i64, 8, write_i64);
+		write_fn!(write_f32, f32, 4, write_f32);
+		write_fn!(write_f64, f64, 8, write_f64);
+	}
+
+	impl<W :Write + ?Sized> WriteBytesExt for W {}
+}
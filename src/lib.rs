@@ -11,41 +11,98 @@
 An Apple Core Audio Format (CAF) container decoder
 
 For more information on CAF, see its [wiki page](https://en.wikipedia.org/wiki/Core_Audio_Format), and the [official specification](https://developer.apple.com/documentation/MusicAudio/Reference/CAFSpec/).
+
+The `std` feature is enabled by default. Without it, the crate is
+`no_std` and only needs `alloc`. The readers and writers are then generic
+over the minimal IO traits of the `io` module, and the functions
+returning a `HashMap` are not available.
 */
 
 #![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
 extern crate byteorder;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 pub mod chunks;
 mod enums;
 mod error;
+pub mod io;
+mod math;
+mod spec;
+mod vlq;
+pub mod pcm;
+pub mod write;
 
 pub use enums::ChunkType;
 pub use enums::FormatType;
+pub use enums::ChannelLayoutTag;
+pub use enums::ChannelLabel;
 pub use error::CafError;
+pub use spec::{SampleSpec, SampleFormat};
+pub use pcm::open_pcm;
+
+/// Raw fourcc values of the chunk and format types named by the spec
+///
+/// Useful for code that works with the raw `u32` values,
+/// e.g. for custom chunk filters.
+/// Usually you'll want to use the `ChunkType` and `FormatType` enums instead.
+pub mod fourcc {
+	pub use enums::chunk_types::*;
+	pub use enums::format_types::*;
+}
 
 use chunks::CafChunk;
 use chunks::CafChunkHeader;
 
-use std::io::{Read, Seek, SeekFrom};
-use byteorder::{BigEndian as Be, ReadBytesExt};
+use io::{Read, Seek, SeekFrom, Take, Cursor, Error as IoError, ErrorKind};
+use io::ReadBytesExt;
+use alloc::vec::Vec;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+use core::ops::Range;
+use core::time::Duration;
+use byteorder::{BigEndian as Be, ByteOrder};
 
 /// The CAF file header
 const CAF_HEADER_MAGIC :[u8; 8] = [0x63, 0x61, 0x66, 0x66, 0x00, 0x01, 0x00, 0x00];
 
+/// The length of a chunk header in bytes
+const HEADER_LEN :i64 = 12;
+
 pub struct CafChunkReader<T> where T :Read {
 	rdr :T,
 }
 
 impl<T> CafChunkReader<T> where T :Read {
 	pub fn new(mut rdr :T) -> Result<Self, CafError> {
+		match try!(CafChunkReader::read_header(&mut rdr)) {
+			(1, 0) => (),
+			_ => return Err(CafError::NotCaf),
+		}
+		Ok(CafChunkReader { rdr : rdr })
+	}
+	/// Reads the CAF file header, returning the file version and flags
+	///
+	/// Returns `CafError::NotCaf` if the file type isn't CAF.
+	/// Version and flags are not checked, so that callers can decide
+	/// themselves whether to proceed. The only version defined by
+	/// the spec is 1, with flags 0. `new` only accepts these values.
+	pub fn read_header(rdr :&mut T) -> Result<(u16, u16), CafError> {
 		let mut hdr_buf = [0;8];
 		try!(rdr.read_exact(&mut hdr_buf));
-		if hdr_buf != CAF_HEADER_MAGIC {
+		if hdr_buf[..4] != CAF_HEADER_MAGIC[..4] {
 			return Err(CafError::NotCaf);
 		}
-		Ok(CafChunkReader { rdr : rdr })
+		let version = Be::read_u16(&hdr_buf[4..6]);
+		let flags = Be::read_u16(&hdr_buf[6..]);
+		Ok((version, flags))
 	}
 	/// Returns the reader that this Reader wraps
 	pub fn into_inner(self) -> T {
@@ -73,32 +130,166 @@ impl<T> CafChunkReader<T> where T :Read {
 		self.read_chunk_body(&hdr)
 	}
 	/// Reads a chunk body into memory and decodes it
+	///
+	/// If the chunk size is unspecified (-1), the chunk
+	/// extends up to the EOF, so everything up to it is read.
 	pub fn read_chunk_body(&mut self, hdr :&CafChunkHeader)
 			-> Result<CafChunk, CafError> {
+		let chunk_content = try!(self.read_chunk_raw(hdr));
+		chunks::decode_chunk(hdr.ch_type, chunk_content)
+	}
+	/// Reads a chunk body into memory, without decoding it
+	///
+	/// Works for all chunk types, including those that `decode_chunk`
+	/// doesn't support. Like with `read_chunk_body`, if the chunk size
+	/// is unspecified (-1), everything up to the EOF is read.
+	pub fn read_chunk_raw(&mut self, hdr :&CafChunkHeader)
+			-> Result<Vec<u8>, CafError> {
+		let mut chunk_content;
 		if hdr.ch_size == -1 {
 			// Unspecified chunk size: this means the chunk is extends up to the EOF.
-			// TODO handle this case
-			panic!("unspecified chunk size is not yet implemented");
+			chunk_content = Vec::new();
+			try!(self.rdr.read_to_end(&mut chunk_content));
+		} else {
+			// Don't trust the size for the allocation,
+			// it might come from a crafted or truncated file
+			chunk_content = Vec::new();
+			let size = hdr.ch_size as u64;
+			try!(self.rdr.by_ref().take(size).read_to_end(&mut chunk_content));
+			if (chunk_content.len() as u64) < size {
+				try!(Err(IoError::new(ErrorKind::UnexpectedEof,
+					"EOF inside of chunk body")));
+			}
 		}
-		let mut chunk_content = vec![0; hdr.ch_size as usize];
-		try!(self.rdr.read_exact(&mut chunk_content));
-		chunks::decode_chunk(hdr.ch_type, chunk_content)
+		Ok(chunk_content)
+	}
+	/// Returns an iterator over all remaining chunks, decoded
+	///
+	/// Works without `Seek`, which means that every chunk, including
+	/// the audio data chunk, needs to be read into memory.
+	/// For large files, this means lots of memory usage.
+	/// If you want to read the audio, use the `CafPacketReader` instead.
+	pub fn into_chunk_iter(self) -> ChunkIter<T> {
+		ChunkIter {
+			ch_rdr : self,
+			done : false,
+		}
+	}
+	/// Reads a chunk header, or returns `None` if at EOF
+	///
+	/// Meant for reading chunks until the end of the file: `None` is
+	/// only returned if the EOF is hit before the first byte of the header.
+	/// EOF inside the header means that the file is truncated,
+	/// and is still returned as an error, like `read_chunk_header` does.
+	pub fn read_chunk_header_opt(&mut self) -> Result<Option<CafChunkHeader>, CafError> {
+		let mut buf = [0; HEADER_LEN as usize];
+		let mut read = 0;
+		while read < buf.len() {
+			match self.rdr.read(&mut buf[read..]) {
+				Ok(0) if read == 0 => return Ok(None),
+				Ok(0) => try!(Err(IoError::new(ErrorKind::UnexpectedEof,
+					"EOF inside of chunk header"))),
+				Ok(n) => read += n,
+				Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+				Err(e) => try!(Err(e)),
+			}
+		}
+		let chunk_size = Be::read_i64(&buf[4..]);
+		if chunk_size < -1 {
+			return Err(CafError::InvalidChunkSize(chunk_size));
+		}
+		Ok(Some(CafChunkHeader {
+			ch_type : ChunkType::from(Be::read_u32(&buf[..4])),
+			ch_size : chunk_size,
+		}))
 	}
 	/// Reads a chunk header
+	///
+	/// Returns `CafError::InvalidChunkSize` if the chunk size
+	/// is negative, but not -1.
 	pub fn read_chunk_header(&mut self) -> Result<CafChunkHeader, CafError> {
 		let chunk_type_u32 = try!(self.rdr.read_u32::<Be>());
 		let chunk_type = ChunkType::from(chunk_type_u32);
-		// TODO return some kind of error if chunk_size < 0 and != -1
 		let chunk_size = try!(self.rdr.read_i64::<Be>());
+		if chunk_size < -1 {
+			return Err(CafError::InvalidChunkSize(chunk_size));
+		}
 		Ok(CafChunkHeader {
 			ch_type : chunk_type,
 			ch_size : chunk_size,
 		})
 	}
+	/// Skips the body of the chunk, without seeking
+	///
+	/// Like `try_to_next_chunk`, but reads and discards the bytes
+	/// instead, so it works for readers that don't support seeking.
+	/// Returns `CafError::UnspecifiedChunkSize` if the chunk size is
+	/// unspecified, and an `UnexpectedEof` io error if the
+	/// file ends before the end of the chunk.
+	pub fn skip_chunk(&mut self, hdr :&CafChunkHeader) -> Result<(), CafError> {
+		if hdr.ch_size == -1 {
+			return Err(CafError::UnspecifiedChunkSize);
+		}
+		let size = hdr.ch_size as u64;
+		let skipped = try!(io::copy(&mut self.rdr.by_ref().take(size),
+			&mut io::sink()));
+		if skipped < size {
+			try!(Err(IoError::new(ErrorKind::UnexpectedEof,
+				"EOF inside of chunk body")));
+		}
+		Ok(())
+	}
 }
 
 impl<T> CafChunkReader<T> where T :Read + Seek {
 
+	/// Returns the current position in the file
+	///
+	/// Directly after reading a chunk header or skipping a chunk, this is
+	/// where the chunk body or the next chunk header starts.
+	pub fn position(&mut self) -> Result<u64, CafError> {
+		Ok(try!(self.rdr.seek(SeekFrom::Current(0))))
+	}
+	/// Reads the next chunk header without advancing the stream
+	///
+	/// Afterwards, the reader is positioned at the start of
+	/// the header again, also if an error occurred.
+	pub fn peek_chunk_header(&mut self) -> Result<CafChunkHeader, CafError> {
+		let pos = try!(self.rdr.seek(SeekFrom::Current(0)));
+		let res = self.read_chunk_header();
+		try!(self.rdr.seek(SeekFrom::Start(pos)));
+		res
+	}
+	/// Returns an iterator over the headers of the remaining chunks
+	///
+	/// The chunk bodies are skipped. The iterator ends at EOF, after a
+	/// chunk with unspecified size, as that one extends up to the EOF,
+	/// or after the first error.
+	/// Like with `walk`, trailing bytes after the last chunk that don't
+	/// form a chunk fitting into the file are ignored.
+	pub fn chunk_headers(self) -> ChunkHeaderIter<T> {
+		ChunkHeaderIter {
+			ch_rdr : self,
+			last_hdr : None,
+			end : None,
+			done : false,
+		}
+	}
+	/// Returns a reader over the body of the chunk with the given header
+	///
+	/// Needs to be called directly after the header has been read.
+	/// The returned reader is limited to the chunk body, so it can be
+	/// used to stream big chunks, like the audio data chunk, without
+	/// reading them into memory.
+	/// Once it gets dropped, the rest of the body is skipped, so that
+	/// this reader is positioned at the next chunk header.
+	pub fn chunk_body_reader<'a>(&'a mut self, hdr :&CafChunkHeader)
+			-> ChunkBodyReader<'a, T> {
+		ChunkBodyReader {
+			rdr : &mut self.rdr,
+			remaining : if hdr.ch_size == -1 { None } else { Some(hdr.ch_size as u64) },
+		}
+	}
 	/**
 	Seeks to the next chunk header in the file
 
@@ -113,11 +304,23 @@ impl<T> CafChunkReader<T> where T :Read + Seek {
 
 	Panics if the header's chunk size is unspecified per spec (==-1).
 	"Skipping" would make no sense here, as it will put you to the end of the file.
+	Use `try_to_next_chunk` if you want an error instead.
 	*/
 	pub fn to_next_chunk(&mut self, hdr :&CafChunkHeader) -> Result<(), CafError> {
-		if hdr.ch_size == -1 {
+		match self.try_to_next_chunk(hdr) {
 			// This would be EOF, makes no sense...
-			panic!("can't seek to end of chunk with unspecified chunk size.");
+			Err(CafError::UnspecifiedChunkSize) =>
+				panic!("can't seek to end of chunk with unspecified chunk size."),
+			res => res,
+		}
+	}
+	/// Seeks to the next chunk header in the file
+	///
+	/// Like `to_next_chunk`, but returns `CafError::UnspecifiedChunkSize`
+	/// instead of panicking if the chunk size is unspecified.
+	pub fn try_to_next_chunk(&mut self, hdr :&CafChunkHeader) -> Result<(), CafError> {
+		if hdr.ch_size == -1 {
+			return Err(CafError::UnspecifiedChunkSize);
 		}
 		try!(self.rdr.seek(SeekFrom::Current(hdr.ch_size)));
 		Ok(())
@@ -131,15 +334,255 @@ impl<T> CafChunkReader<T> where T :Read + Seek {
 
 	Panics if the header's chunk size is unspecified per spec (==-1).
 	"Skipping" would make no sense here, as it will put you to the end of the file.
+	Use `try_to_previous_chunk` if you want an error instead.
 	*/
 	pub fn to_previous_chunk(&mut self, hdr :&CafChunkHeader) -> Result<(), CafError> {
-		if hdr.ch_size == -1 {
+		match self.try_to_previous_chunk(hdr) {
 			// This would be EOF, makes no sense...
-			panic!("can't seek to end of chunk with unspecified chunk size.");
+			Err(CafError::UnspecifiedChunkSize) =>
+				panic!("can't seek to end of chunk with unspecified chunk size."),
+			res => res,
+		}
+	}
+	/// Seeks to the start of the previous chunk body in the file
+	///
+	/// Like `to_previous_chunk`, but returns `CafError::UnspecifiedChunkSize`
+	/// instead of panicking if the chunk size is unspecified.
+	pub fn try_to_previous_chunk(&mut self, hdr :&CafChunkHeader) -> Result<(), CafError> {
+		if hdr.ch_size == -1 {
+			return Err(CafError::UnspecifiedChunkSize);
 		}
 		try!(self.rdr.seek(SeekFrom::Current(-hdr.ch_size)));
 		Ok(())
 	}
+	/// Seeks to the next chunk with the given type
+	///
+	/// Reads chunk headers, starting at the current position, which needs
+	/// to be at the start of a chunk header, and skips the chunks until one
+	/// with the given type is found. Its header is returned, with the reader
+	/// positioned at the start of its body.
+	///
+	/// Returns `None` if the EOF is reached, or a chunk with unspecified
+	/// size and a different type is encountered, as nothing can follow it.
+	pub fn seek_to_chunk(&mut self, ch_type :ChunkType)
+			-> Result<Option<CafChunkHeader>, CafError> {
+		while let Some(hdr) = try!(self.read_chunk_header_opt()) {
+			if hdr.ch_type == ch_type {
+				return Ok(Some(hdr));
+			}
+			if hdr.ch_size == -1 {
+				// The chunk extends up to the EOF
+				return Ok(None);
+			}
+			try!(self.to_next_chunk(&hdr));
+		}
+		Ok(None)
+	}
+
+	/**
+	Checks whether the audio data chunk is the last chunk in the file
+
+	Walks the chunk headers, starting at the current position, which
+	needs to be at the start of a chunk header (e.g. directly after
+	creating the reader).
+	Once done, the reader is put back to the position it was at.
+
+	If this returns `false` for a file with an audio data chunk, there
+	is metadata stored after the audio data.
+	Audio data chunks with unspecified size are always last.
+	Returns `false` as well if no audio data chunk could be found.
+	*/
+	pub fn data_is_last(&mut self) -> Result<bool, CafError> {
+		let start = try!(self.rdr.seek(SeekFrom::Current(0)));
+		let res = self.data_is_last_inner(start);
+		try!(self.rdr.seek(SeekFrom::Start(start)));
+		res
+	}
+	fn data_is_last_inner(&mut self, start :u64) -> Result<bool, CafError> {
+		let end = try!(self.rdr.seek(SeekFrom::End(0)));
+		try!(self.rdr.seek(SeekFrom::Start(start)));
+		let mut data_found = false;
+		while let Some(hdr) = try!(self.read_chunk_header_before(end)) {
+			if data_found {
+				// Another chunk follows the audio data chunk
+				return Ok(false);
+			}
+			if hdr.ch_type == ChunkType::AudioData {
+				if hdr.ch_size == -1 {
+					return Ok(true);
+				}
+				data_found = true;
+			} else if hdr.ch_size == -1 {
+				return Ok(false);
+			}
+			try!(self.to_next_chunk(&hdr));
+		}
+		Ok(data_found)
+	}
+	/// Returns the length of the underlying reader, keeping its position
+	fn stream_len(&mut self) -> Result<u64, CafError> {
+		let pos = try!(self.rdr.seek(SeekFrom::Current(0)));
+		let end = try!(self.rdr.seek(SeekFrom::End(0)));
+		try!(self.rdr.seek(SeekFrom::Start(pos)));
+		Ok(end)
+	}
+	/// Reads a chunk header, if a valid chunk fits before `end`
+	///
+	/// Returns `None` if the bytes up to `end` can't hold a chunk header,
+	/// or if the chunk it describes would extend past `end`.
+	/// Such bytes are treated as trailing garbage after the last chunk,
+	/// and the reader is put back to the position it was at.
+	fn read_chunk_header_before(&mut self, end :u64)
+			-> Result<Option<CafChunkHeader>, CafError> {
+		let pos = try!(self.rdr.seek(SeekFrom::Current(0)));
+		if pos + HEADER_LEN as u64 > end {
+			return Ok(None);
+		}
+		let hdr = match self.read_chunk_header() {
+			Ok(hdr) => hdr,
+			Err(CafError::InvalidChunkSize(_)) => {
+				try!(self.rdr.seek(SeekFrom::Start(pos)));
+				return Ok(None);
+			},
+			Err(e) => return Err(e),
+		};
+		let remaining = end - pos - HEADER_LEN as u64;
+		let fits = hdr.ch_size == -1 ||
+			(hdr.ch_size >= 0 && hdr.ch_size as u64 <= remaining);
+		if !fits {
+			try!(self.rdr.seek(SeekFrom::Start(pos)));
+			return Ok(None);
+		}
+		Ok(Some(hdr))
+	}
+
+	/**
+	Walks the chunks of the file, calling `f` for each of them
+
+	Starts at the current position, which needs to be at the start of a
+	chunk header.
+	For each chunk, `f` is passed the chunk header and a `ChunkBody`
+	through which the chunk's content can be read to memory or
+	streamed. If the `ChunkBody` is dropped without being used, the
+	chunk is skipped.
+	Either way, the walker continues with the next chunk,
+	unless `f` returns `WalkAction::Stop`, or the end of the file
+	was reached.
+	Trailing bytes after the last chunk that don't form a chunk
+	fitting into the file are ignored.
+
+	After stopping, the reader is positioned at the start of the
+	chunk following the last visited one.
+	*/
+	pub fn walk<F>(&mut self, mut f :F) -> Result<(), CafError>
+			where F :FnMut(&CafChunkHeader, ChunkBody<T>) -> WalkAction {
+		let mut pos = try!(self.rdr.seek(SeekFrom::Current(0))) as i64;
+		let end = try!(self.rdr.seek(SeekFrom::End(0)));
+		try!(self.rdr.seek(SeekFrom::Start(pos as u64)));
+		while let Some(hdr) = try!(self.read_chunk_header_before(end)) {
+			let action = f(&hdr, ChunkBody { ch_rdr : self, hdr : &hdr });
+			if hdr.ch_size == -1 {
+				// The chunk extends up to the EOF
+				break;
+			}
+			pos += HEADER_LEN + hdr.ch_size;
+			try!(self.rdr.seek(SeekFrom::Start(pos as u64)));
+			match action {
+				WalkAction::Continue => (),
+				WalkAction::Stop => break,
+			}
+		}
+		Ok(())
+	}
+
+	/**
+	Returns a summary of the optional features used by the file
+
+	Walks the chunks of the file, starting at the current position,
+	which needs to be at the start of a chunk header (e.g. directly
+	after creating the reader).
+	Once done, the reader is put back to the position it was at.
+	*/
+	pub fn feature_summary(&mut self) -> Result<FeatureSummary, CafError> {
+		let start = try!(self.rdr.seek(SeekFrom::Current(0)));
+		let mut res = FeatureSummary::default();
+		let mut desc_res = None;
+		let walk_res = self.walk(|hdr, body| {
+			match hdr.ch_type {
+				ChunkType::AudioDescription => desc_res = Some(body.read()),
+				ChunkType::AudioData => res.is_streaming_layout = hdr.ch_size == -1,
+				ChunkType::Marker => res.has_markers = true,
+				ChunkType::Region => res.has_regions = true,
+				ChunkType::ChannelLayout => res.has_channel_layout = true,
+				ChunkType::MagicCookie => res.has_magic_cookie = true,
+				ChunkType::Info => res.has_info = true,
+				_ => (),
+			}
+			WalkAction::Continue
+		});
+		try!(self.rdr.seek(SeekFrom::Start(start)));
+		try!(walk_res);
+		match desc_res {
+			Some(Ok(CafChunk::Desc(desc))) => {
+				res.is_vbr = desc.bytes_per_packet == 0;
+			},
+			Some(Err(e)) => return Err(e),
+			_ => return Err(CafError::MissingChunk(ChunkType::AudioDescription)),
+		}
+		Ok(res)
+	}
+
+	/// Reads the header of the packet table at the given position
+	///
+	/// The packet lengths are not read, they can be read
+	/// on demand through the returned state.
+	/// Afterwards, the reader is put back to the position it was at.
+	fn read_lazy_packet_table(&mut self, pos :u64, len :u64)
+			-> Result<(chunks::PacketTable, LazyPacketLengths), CafError> {
+		let cur = try!(self.rdr.seek(SeekFrom::Current(0)));
+		try!(self.rdr.seek(SeekFrom::Start(pos)));
+		let num_packets = try!(self.rdr.read_i64::<Be>());
+		let table = chunks::PacketTable {
+			num_valid_frames : try!(self.rdr.read_i64::<Be>()),
+			num_priming_frames : try!(self.rdr.read_i32::<Be>()),
+			num_remainder_frames : try!(self.rdr.read_i32::<Be>()),
+			lengths : Vec::new(),
+		};
+		try!(self.rdr.seek(SeekFrom::Start(cur)));
+		// The size of the fields read above
+		const TABLE_HEADER_LEN :u64 = 24;
+		let lengths = LazyPacketLengths {
+			first_pos : pos + TABLE_HEADER_LEN,
+			end_pos : pos + len,
+			num_packets : ::core::cmp::max(num_packets, 0) as usize,
+			next_pos : pos + TABLE_HEADER_LEN,
+			buf_first_idx : 0,
+			buf : Vec::new(),
+		};
+		Ok((table, lengths))
+	}
+
+	/**
+	Returns how many chunks of each type the file contains
+
+	Walks the chunks of the file, starting at the current position,
+	which needs to be at the start of a chunk header (e.g. directly
+	after creating the reader).
+	Once done, the reader is put back to the position it was at.
+	Only available with the `std` feature.
+	*/
+	#[cfg(feature = "std")]
+	pub fn chunk_type_counts(&mut self) -> Result<HashMap<ChunkType, usize>, CafError> {
+		let start = try!(self.rdr.seek(SeekFrom::Current(0)));
+		let mut res = HashMap::new();
+		let walk_res = self.walk(|hdr, _| {
+			*res.entry(hdr.ch_type).or_insert(0) += 1;
+			WalkAction::Continue
+		});
+		try!(self.rdr.seek(SeekFrom::Start(start)));
+		try!(walk_res);
+		Ok(res)
+	}
 
 	/**
 	Read chunks from a whitelist to memory
@@ -148,6 +591,11 @@ impl<T> CafChunkReader<T> where T :Read + Seek {
 	whose types are inside the `content_read` slice.
 	Stops as soon as all chunk were encountered with types in the
 	`required` argument list.
+	If the end of the file, or a chunk with unspecified size, is
+	reached before that, `CafError::MissingChunk` is returned
+	with one of the chunk types that weren't found.
+	If `required` is empty, at most one chunk is read, and
+	no error is returned if the reader is already at the end of the file.
 
 	As we don't have support for reading chunks with unspecified length,
 	you shouldn't use this function to read audio data to memory.
@@ -161,7 +609,14 @@ impl<T> CafChunkReader<T> where T :Read + Seek {
 		let mut res = Vec::with_capacity(content_read.len());
 		let mut read_headers = Vec::new();
 		loop {
-			let hdr = try!(self.read_chunk_header());
+			let hdr = match try!(self.read_chunk_header_opt()) {
+				Some(hdr) => hdr,
+				None => match required.first() {
+					Some(&ch_type) => return Err(CafError::MissingChunk(ch_type)),
+					// Nothing was required, so reaching the end is fine
+					None => break,
+				},
+			};
 			let mut required_idx = None;
 			let mut content_read_found = false;
 			for (i, &searched_type) in required.iter().enumerate() {
@@ -176,22 +631,20 @@ impl<T> CafChunkReader<T> where T :Read + Seek {
 					break;
 				}
 			}
-			if hdr.ch_size == -1 {
-				// TODO: return an error.
+			match required_idx { None => (), Some(i) => { required.remove(i); } }
+			if hdr.ch_size == -1 && required.len() > 0 {
 				/*
-				We don't support chunks with unspecified (=-1) length.
-				Reading such a chunk to memory would be a bad idea as they
-				can possibly be gigantic, and are only used for the audio chunk,
-				which is a very uninteresting target to be read to memory anyways.
+				Chunks with unspecified (=-1) length are only used for
+				the audio chunk, which is a very uninteresting target to be read
+				to memory anyways, as it can possibly be gigantic.
 				Also, such chunks are only found at the end of the file, and if we
 				encounter them it means we didn't find the chunks we searched for.
 				*/
+				return Err(CafError::MissingChunk(required[0]));
 			}
-
-			match required_idx { None => (), Some(i) => { required.remove(i); } }
 			if content_read_found {
 				res.push(try!(self.read_chunk_body(&hdr)));
-			} else {
+			} else if hdr.ch_size != -1 {
 				try!(self.to_next_chunk(&hdr));
 			}
 			read_headers.push(hdr.clone());
@@ -203,6 +656,225 @@ impl<T> CafChunkReader<T> where T :Read + Seek {
 	}
 }
 
+/**
+Reads the key/value pairs of the Info chunks
+
+Returns an empty list if the file has no Info chunk.
+If there are multiple Info chunks, their entries are merged,
+like `CafPacketReader::all_info` does.
+All other chunks are skipped without reading their content,
+so the audio data isn't read, even if an Info chunk comes after it.
+*/
+pub fn read_info<T>(rdr :T) -> Result<Vec<(String, String)>, CafError> where T :Read + Seek {
+	let mut ch_rdr = try!(CafChunkReader::new(rdr));
+	let mut res = Vec::new();
+	while let Some(hdr) = try!(ch_rdr.read_chunk_header_opt()) {
+		if hdr.ch_type == ChunkType::Info {
+			if let CafChunk::Info(entries) = try!(ch_rdr.read_chunk_body(&hdr)) {
+				merge_info(&mut res, &entries);
+			}
+			continue;
+		}
+		if hdr.ch_size == -1 {
+			// The chunk extends to the EOF, nothing comes after it.
+			break;
+		}
+		try!(ch_rdr.to_next_chunk(&hdr));
+	}
+	Ok(res)
+}
+
+/// Adds the entries to `res`, replacing the values of keys already present
+fn merge_info(res :&mut Vec<(String, String)>, entries :&[(String, String)]) {
+	for &(ref key, ref val) in entries.iter() {
+		match res.iter().position(|&(ref k, _)| k == key) {
+			Some(i) => res[i].1 = val.clone(),
+			None => res.push((key.clone(), val.clone())),
+		}
+	}
+}
+
+/// Iterator over decoded chunks, returned by `CafChunkReader::into_chunk_iter`
+///
+/// Ends at EOF, or after the first error.
+pub struct ChunkIter<T> where T :Read {
+	ch_rdr :CafChunkReader<T>,
+	done :bool,
+}
+
+impl<T> Iterator for ChunkIter<T> where T :Read {
+	type Item = Result<CafChunk, CafError>;
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+		let res = match self.ch_rdr.read_chunk_header_opt() {
+			Ok(Some(hdr)) => self.ch_rdr.read_chunk_body(&hdr),
+			Ok(None) => {
+				self.done = true;
+				return None;
+			},
+			Err(e) => Err(e),
+		};
+		if res.is_err() {
+			self.done = true;
+		}
+		Some(res)
+	}
+}
+
+/// Iterator over chunk headers, returned by `CafChunkReader::chunk_headers`
+pub struct ChunkHeaderIter<T> where T :Read + Seek {
+	ch_rdr :CafChunkReader<T>,
+	/// The header returned last, whose body still needs to be skipped
+	last_hdr :Option<CafChunkHeader>,
+	/// The length of the file, determined on the first call to `next`
+	end :Option<u64>,
+	done :bool,
+}
+
+impl<T> Iterator for ChunkHeaderIter<T> where T :Read + Seek {
+	type Item = Result<CafChunkHeader, CafError>;
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+		if let Some(hdr) = self.last_hdr.take() {
+			if hdr.ch_size == -1 {
+				self.done = true;
+				return None;
+			}
+			if let Err(e) = self.ch_rdr.to_next_chunk(&hdr) {
+				self.done = true;
+				return Some(Err(e));
+			}
+		}
+		let end = match self.end {
+			Some(end) => Ok(end),
+			None => self.ch_rdr.stream_len(),
+		};
+		let res = end.and_then(|end| {
+			self.end = Some(end);
+			self.ch_rdr.read_chunk_header_before(end)
+		});
+		match res {
+			Ok(Some(hdr)) => {
+				self.last_hdr = Some(hdr.clone());
+				Some(Ok(hdr))
+			},
+			Ok(None) => {
+				self.done = true;
+				None
+			},
+			Err(e) => {
+				self.done = true;
+				Some(Err(e))
+			},
+		}
+	}
+}
+
+/// Reader over a chunk body, returned by `CafChunkReader::chunk_body_reader`
+pub struct ChunkBodyReader<'a, T> where T :Read + Seek + 'a {
+	rdr :&'a mut T,
+	/// `None` if the chunk size is unspecified
+	remaining :Option<u64>,
+}
+
+impl<'a, T> Read for ChunkBodyReader<'a, T> where T :Read + Seek + 'a {
+	fn read(&mut self, buf :&mut [u8]) -> io::Result<usize> {
+		let len = match self.remaining {
+			Some(r) => ::core::cmp::min(r, buf.len() as u64) as usize,
+			None => buf.len(),
+		};
+		let read = try!(self.rdr.read(&mut buf[..len]));
+		if let Some(ref mut r) = self.remaining {
+			*r -= read as u64;
+		}
+		Ok(read)
+	}
+}
+
+impl<'a, T> Drop for ChunkBodyReader<'a, T> where T :Read + Seek + 'a {
+	fn drop(&mut self) {
+		match self.remaining {
+			Some(r) if r > 0 => {
+				// Errors can't be reported here. They will surface
+				// once the next chunk header gets read.
+				let _ = self.rdr.seek(SeekFrom::Current(r as i64));
+			},
+			_ => (),
+		}
+	}
+}
+
+/// Summary of the optional features of a file
+///
+/// Returned by `CafChunkReader::feature_summary`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeatureSummary {
+	/// Whether the file contains a marker chunk
+	pub has_markers :bool,
+	/// Whether the file contains a region chunk
+	pub has_regions :bool,
+	/// Whether the file contains a channel layout chunk
+	pub has_channel_layout :bool,
+	/// Whether the file contains a magic cookie chunk
+	pub has_magic_cookie :bool,
+	/// Whether the file contains an info chunk
+	pub has_info :bool,
+	/// Whether the packets have a variable size
+	pub is_vbr :bool,
+	/// Whether the audio data chunk has an unspecified size,
+	/// as written by streaming writers
+	pub is_streaming_layout :bool,
+}
+
+/// Whether `CafChunkReader::walk` should continue with the next chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkAction {
+	Continue,
+	Stop,
+}
+
+/// Access to the content of a chunk visited by `CafChunkReader::walk`
+pub struct ChunkBody<'a, T> where T :Read + Seek + 'a {
+	ch_rdr :&'a mut CafChunkReader<T>,
+	hdr :&'a CafChunkHeader,
+}
+
+impl<'a, T> ChunkBody<'a, T> where T :Read + Seek + 'a {
+	/// Reads the chunk's content into memory and decodes it
+	pub fn read(self) -> Result<CafChunk, CafError> {
+		self.ch_rdr.read_chunk_body(self.hdr)
+	}
+	/// Returns a reader over the chunk's content
+	///
+	/// The reader is limited to the chunk's content,
+	/// so it can be used to stream big chunks.
+	pub fn reader(self) -> Take<&'a mut T> {
+		let limit = if self.hdr.ch_size == -1 {
+			::core::u64::MAX
+		} else {
+			self.hdr.ch_size as u64
+		};
+		(&mut self.ch_rdr.rdr).take(limit)
+	}
+}
+
+/**
+A source of audio packets
+
+Allows decoders to be written generically over the way the
+packets are obtained.
+*/
+pub trait PacketSource {
+	/// Returns the next packet, or `None` if all packets were read
+	fn next_packet(&mut self) -> Result<Option<Vec<u8>>, CafError>;
+	/// Returns the description of the audio the packets belong to
+	fn audio_description(&self) -> &chunks::AudioDescription;
+}
+
 /**
 High level Packet reading
 
@@ -217,7 +889,41 @@ pub struct CafPacketReader<T> where T :Read + Seek {
 	pub edit_count :u32,
 	audio_chunk_len :i64,
 	audio_chunk_offs :i64,
+	/// Position of the first packet in the file
+	audio_data_start :u64,
 	packet_idx :usize,
+	/// Buffer reused by `next_packet_ref`
+	packet_buf :Vec<u8>,
+	/// Only present if the packet table is read lazily
+	lazy_lengths :Option<LazyPacketLengths>,
+	/// Set via `set_frames_per_packet`
+	frames_per_packet_override :Option<u32>,
+}
+
+/// Checks that the audio data chunk can hold the edit count
+fn check_audio_chunk_len(audio_chunk_len :i64) -> Result<(), CafError> {
+	if audio_chunk_len != -1 && audio_chunk_len < 4 {
+		return Err(CafError::Io(IoError::new(ErrorKind::InvalidData,
+			"audio data chunk too small for the edit count")));
+	}
+	Ok(())
+}
+
+/// The number of packet lengths to read at once in lazy mode
+const LAZY_LENGTHS_BATCH :usize = 1024;
+
+/// State for reading the packet lengths from the packet table on demand
+struct LazyPacketLengths {
+	/// Position of the first packet length in the file
+	first_pos :u64,
+	/// Position of the end of the packet table chunk in the file
+	end_pos :u64,
+	num_packets :usize,
+	/// Position of the first length in the file that is not in `buf`
+	next_pos :u64,
+	/// Index of the packet whose length is first in `buf`
+	buf_first_idx :usize,
+	buf :Vec<u64>,
 }
 
 impl<T> CafPacketReader<T> where T :Read + Seek {
@@ -231,6 +937,10 @@ impl<T> CafPacketReader<T> where T :Read + Seek {
 	/// the `AudioData` chunk), and through the `audio_desc` and `packet_table`
 	/// members.
 	///
+	/// If the file lacks a chunk of one of the passed types,
+	/// `CafError::MissingChunk` is returned. Info chunks are an exception:
+	/// all of them are kept, and the file doesn't need to contain any.
+	///
 	/// Equal to calling `CafChunkReader::new` and passing its result to
 	/// `from_chunk_reader`.
 	pub fn new(rdr :T, filter_by :Vec<ChunkType>) -> Result<Self, CafError> {
@@ -238,6 +948,20 @@ impl<T> CafPacketReader<T> where T :Read + Seek {
 		return CafPacketReader::from_chunk_reader(ch_rdr, filter_by);
 	}
 
+	/// Creates a new CAF packet reader that reads the packet table lazily
+	///
+	/// Like `new`, but instead of reading the whole packet table
+	/// to memory, only the header of the packet table is read, and
+	/// the packet lengths are read in batches as the packets are consumed.
+	/// This saves memory for files with a large number of packets,
+	/// at the cost of additional seeks.
+	///
+	/// The `lengths` member of the `packet_table` will be empty.
+	pub fn new_lazy(rdr :T, filter_by :Vec<ChunkType>) -> Result<Self, CafError> {
+		let ch_rdr = try!(CafChunkReader::new(rdr));
+		return CafPacketReader::from_chunk_reader_inner(ch_rdr, filter_by, Vec::new(), true);
+	}
+
 	/// Creates a new CAF packet reader struct from a given chunk reader.
 	///
 	/// With the `filter_by` argument you can pass a list of chunk types
@@ -247,15 +971,58 @@ impl<T> CafPacketReader<T> where T :Read + Seek {
 	/// through iterating over the packets (which are all small parts of
 	/// the `AudioData` chunk), and through the `audio_desc` and `packet_table`
 	/// members.
-	pub fn from_chunk_reader(mut ch_rdr :CafChunkReader<T>,
-			mut filter_by :Vec<ChunkType>) -> Result<Self, CafError> {
+	pub fn from_chunk_reader(ch_rdr :CafChunkReader<T>,
+			filter_by :Vec<ChunkType>) -> Result<Self, CafError> {
+		CafPacketReader::from_chunk_reader_inner(ch_rdr, filter_by, Vec::new(), false)
+	}
+
+	/// Chunks in `filter_by` are required, while those in `optional`
+	/// are only kept if the file contains them.
+	fn from_chunk_reader_inner(mut ch_rdr :CafChunkReader<T>,
+			mut filter_by :Vec<ChunkType>, mut optional :Vec<ChunkType>,
+			lazy :bool) -> Result<Self, CafError> {
+		let start_pos = try!(ch_rdr.rdr.seek(SeekFrom::Current(0)));
+
+		// Info chunks are never required, so that `all_info` works
+		// for files without any
+		if filter_by.contains(&ChunkType::Info) {
+			filter_by.retain(|t| *t != ChunkType::Info);
+			optional.push(ChunkType::Info);
+		}
 
 		// 1. Read all the chunks we need to memory
 		filter_by.push(ChunkType::AudioDescription);
 		let mut content_read = filter_by.clone();
-		content_read.push(ChunkType::PacketTable);
+		content_read.extend_from_slice(&optional);
+		if !lazy {
+			content_read.push(ChunkType::PacketTable);
+		}
+		let keep_all_info = content_read.contains(&ChunkType::Info);
 		let (mut chunks_in_mem, mut read_headers) =
 			try!(ch_rdr.read_chunks_to_mem(filter_by, &content_read));
+		if keep_all_info || optional.len() > 0 {
+			// Optional chunks may come after the required ones,
+			// and a file may contain multiple info chunks.
+			// Collect all of them.
+			while let Some(hdr) = try!(ch_rdr.read_chunk_header_opt()) {
+				if hdr.ch_size == -1 {
+					// Nothing can follow this chunk. Go back to its
+					// header so that it can be found again below.
+					try!(ch_rdr.rdr.seek(SeekFrom::Current(-HEADER_LEN)));
+					break;
+				}
+				// Chunks like the packet table might not have been read yet
+				let first_of_type = !read_headers.iter()
+					.any(|h| h.ch_type == hdr.ch_type);
+				if (keep_all_info && hdr.ch_type == ChunkType::Info) ||
+						(first_of_type && content_read.contains(&hdr.ch_type)) {
+					chunks_in_mem.push(try!(ch_rdr.read_chunk_body(&hdr)));
+				} else {
+					try!(ch_rdr.to_next_chunk(&hdr));
+				}
+				read_headers.push(hdr);
+			}
+		}
 
 		// 2. Extract the special chunks we will need later on
 		let mut audio_desc_idx = None;
@@ -277,10 +1044,46 @@ impl<T> CafPacketReader<T> where T :Read + Seek {
 				}
 			}
 		}
-		let audio_desc = remove_and_unwrap!(audio_desc_idx.unwrap(), Desc);
+		let audio_desc_idx = audio_desc_idx.unwrap();
+		let audio_desc = remove_and_unwrap!(audio_desc_idx, Desc);
+		// The removal shifts the chunks after the audio description
+		let mut packet_table_idx = packet_table_idx
+			.map(|i| if i > audio_desc_idx { i - 1 } else { i });
 		let p_table_required = audio_desc.bytes_per_packet == 0 ||
 			audio_desc.frames_per_packet == 0;
+		let mut lazy_lengths = None;
 		let packet_table = match packet_table_idx {
+			_ if lazy => {
+				let has_p_table = read_headers.iter()
+					.any(|hdr| hdr.ch_type == ChunkType::PacketTable);
+				if !has_p_table && p_table_required {
+					let (chunks, hdrs) =  try!(ch_rdr.read_chunks_to_mem(
+							vec![ChunkType::PacketTable],
+							&content_read));
+					chunks_in_mem.extend_from_slice(&chunks);
+					read_headers.extend_from_slice(&hdrs);
+				}
+				// Find out where the packet table is in the file
+				let mut pos = start_pos as i64;
+				let mut p_table_pos = None;
+				for hdr in read_headers.iter() {
+					pos += HEADER_LEN;
+					if hdr.ch_type == ChunkType::PacketTable {
+						p_table_pos = Some((pos as u64, hdr.ch_size as u64));
+						break;
+					}
+					pos += hdr.ch_size;
+				}
+				match p_table_pos {
+					Some((p_table_pos, p_table_len)) => {
+						let (table, lengths) = try!(ch_rdr.read_lazy_packet_table(
+							p_table_pos, p_table_len));
+						lazy_lengths = Some(lengths);
+						Some(table)
+					},
+					None => None,
+				}
+			},
 			Some(i) => Some(remove_and_unwrap!(i, PacketTable)),
 			None if p_table_required => {
 				let (chunks, hdrs) =  try!(ch_rdr.read_chunks_to_mem(
@@ -306,7 +1109,6 @@ impl<T> CafPacketReader<T> where T :Read + Seek {
 		// If yes, calculate the amount to seek back to get to it.
 		let mut audio_chunk_len = 0;
 		let mut seek_backwards = 0;
-		const HEADER_LEN :i64 = 12;
 		for hdr in read_headers.iter() {
 			if seek_backwards > 0 || hdr.ch_type == ChunkType::AudioData {
 				seek_backwards += HEADER_LEN;
@@ -335,12 +1137,14 @@ impl<T> CafPacketReader<T> where T :Read + Seek {
 			}
 		}
 		// 4. Read the edit count
+		try!(check_audio_chunk_len(audio_chunk_len));
 		let edit_count = {
-			use byteorder::{ReadBytesExt, BigEndian};
+			use byteorder::BigEndian;
 			try!(ch_rdr.rdr.read_u32::<BigEndian>())
 		};
+		let audio_data_start = try!(ch_rdr.rdr.seek(SeekFrom::Current(0)));
 		// 5. Return the result
-		Ok(CafPacketReader {
+		let mut res = CafPacketReader {
 			ch_rdr : ch_rdr,
 			audio_desc : audio_desc,
 			packet_table : packet_table,
@@ -348,12 +1152,145 @@ impl<T> CafPacketReader<T> where T :Read + Seek {
 			edit_count : edit_count,
 			audio_chunk_len : audio_chunk_len,
 			audio_chunk_offs : 4, // 4 bytes for the edit count.
+			audio_data_start : audio_data_start,
 			packet_idx : 0,
+			packet_buf : Vec::new(),
+			lazy_lengths : lazy_lengths,
+			frames_per_packet_override : None,
+		};
+		try!(res.load_packet_length(0));
+		Ok(res)
+	}
+	/// Creates a packet reader from already parsed metadata
+	///
+	/// Meant for callers that obtained the metadata from elsewhere, e.g.
+	/// from an earlier parse of the file, and don't want the container
+	/// to be scanned again.
+	/// `rdr` needs to be positioned at the start of the body of the audio
+	/// data chunk, which starts with the edit count.
+	/// `audio_chunk_len` is the size of the audio data chunk as given in
+	/// its header, including the edit count, or -1 if it is unspecified.
+	///
+	/// If the format requires a packet table and none is given,
+	/// `CafError::MissingChunk` is returned. If the packet table describes
+	/// more data than the audio data chunk holds, an `InvalidData`
+	/// io error is returned.
+	pub fn from_parts(mut rdr :T, audio_desc :chunks::AudioDescription,
+			packet_table :Option<chunks::PacketTable>, audio_chunk_len :i64)
+			-> Result<Self, CafError> {
+		let p_table_required = audio_desc.bytes_per_packet == 0 ||
+			audio_desc.frames_per_packet == 0;
+		if p_table_required && packet_table.is_none() {
+			return Err(CafError::MissingChunk(ChunkType::PacketTable));
+		}
+		if audio_chunk_len != -1 {
+			let packets_len = match packet_table {
+				Some(ref t) if audio_desc.bytes_per_packet == 0 =>
+					t.lengths.iter().fold(Some(0u64), |s, v| s.and_then(|s| s.checked_add(*v))),
+				_ => Some(0),
+			};
+			let fits = match packets_len {
+				Some(l) => audio_chunk_len >= 4 && l <= audio_chunk_len as u64 - 4,
+				None => false,
+			};
+			if !fits {
+				return Err(CafError::Io(IoError::new(ErrorKind::InvalidData,
+					"audio data chunk too small for the given packet table")));
+			}
+		}
+		let edit_count = try!(rdr.read_u32::<Be>());
+		let audio_data_start = try!(rdr.seek(SeekFrom::Current(0)));
+		Ok(CafPacketReader {
+			ch_rdr : CafChunkReader { rdr : rdr },
+			audio_desc : audio_desc,
+			packet_table : packet_table,
+			chunks : Vec::new(),
+			edit_count : edit_count,
+			audio_chunk_len : audio_chunk_len,
+			audio_chunk_offs : 4, // 4 bytes for the edit count.
+			audio_data_start : audio_data_start,
+			packet_idx : 0,
+			packet_buf : Vec::new(),
+			lazy_lengths : None,
+			frames_per_packet_override : None,
 		})
 	}
 	pub fn into_inner(self) -> CafChunkReader<T> {
 		self.ch_rdr
 	}
+	/// Returns the length of the packet with the given index,
+	/// as stored in the packet table
+	///
+	/// In lazy mode, only lengths loaded through
+	/// `load_packet_length` are available.
+	fn packet_length(&self, idx :usize) -> Option<u64> {
+		match (&self.lazy_lengths, &self.packet_table) {
+			(&Some(ref l), _) if idx >= l.buf_first_idx =>
+				l.buf.get(idx - l.buf_first_idx).map(|v| *v),
+			(&Some(_), _) => None,
+			(&None, &Some(ref t)) => t.lengths.get(idx).map(|v| *v),
+			(&None, &None) => None,
+		}
+	}
+	/// Makes the length of the packet with the given index available
+	///
+	/// Only does something in lazy mode, where it reads
+	/// a batch of lengths from the packet table if needed.
+	fn load_packet_length(&mut self, idx :usize) -> Result<(), CafError> {
+		let l = match self.lazy_lengths {
+			Some(ref mut l) => l,
+			None => return Ok(()),
+		};
+		if idx >= l.num_packets ||
+				(idx >= l.buf_first_idx && idx < l.buf_first_idx + l.buf.len()) {
+			return Ok(());
+		}
+		if idx < l.buf_first_idx {
+			// Start over from the beginning of the table
+			l.buf_first_idx = 0;
+			l.buf.clear();
+			l.next_pos = l.first_pos;
+		}
+		let rdr = &mut self.ch_rdr.rdr;
+		let cur = try!(rdr.seek(SeekFrom::Current(0)));
+		while idx >= l.buf_first_idx + l.buf.len() {
+			l.buf_first_idx += l.buf.len();
+			l.buf.clear();
+			let count = ::core::cmp::min(LAZY_LENGTHS_BATCH,
+				l.num_packets - l.buf_first_idx);
+			// A length takes up at most 10 bytes
+			let max_bytes = ::core::cmp::min(count as u64 * 10,
+				l.end_pos.saturating_sub(l.next_pos));
+			let mut bytes = Vec::new();
+			try!(rdr.seek(SeekFrom::Start(l.next_pos)));
+			try!((&mut *rdr).take(max_bytes).read_to_end(&mut bytes));
+			let mut bytes_rdr = Cursor::new(&bytes);
+			for _ in 0 .. count {
+				l.buf.push(try!(vlq::read_vlq(&mut bytes_rdr)));
+			}
+			l.next_pos += bytes_rdr.position();
+		}
+		try!(rdr.seek(SeekFrom::Start(cur)));
+		Ok(())
+	}
+	/// Overrides the number of frames per packet used for computations
+	///
+	/// Some files have a wrong or zero `frames_per_packet` value in their
+	/// audio description. This sets the value to use instead for the
+	/// functions that map between frames or time and packets,
+	/// like `duration_seconds` or `byte_offset_of_frame`.
+	/// The `audio_desc` member itself isn't changed, and neither is
+	/// the way the packets are read, as packet sizes are given in bytes.
+	pub fn set_frames_per_packet(&mut self, frames_per_packet :u32) {
+		self.frames_per_packet_override = Some(frames_per_packet);
+	}
+	/// The number of frames per packet, honoring `set_frames_per_packet`
+	fn frames_per_packet(&self) -> u32 {
+		match self.frames_per_packet_override {
+			Some(v) => v,
+			None => self.audio_desc.frames_per_packet,
+		}
+	}
 	/// Returns whether the size of the packets doesn't change
 	///
 	/// Some formats have a constant, not changing packet size
@@ -370,23 +1307,44 @@ impl<T> CafPacketReader<T> where T :Read + Seek {
 	/// slice yourself.
 	pub fn next_packet_size(&self) -> Option<usize> {
 		let res = match self.audio_desc.bytes_per_packet {
-			0 => match self.packet_table.as_ref()
-					.unwrap().lengths.get(self.packet_idx) {
-				Some(v) => *v as usize,
+			0 => match self.packet_length(self.packet_idx) {
+				Some(v) => v,
 				None => return None,
 			},
-			v => v as usize,
+			v => v as u64,
 		};
-		if self.audio_chunk_len != -1 &&
-				self.audio_chunk_offs + res as i64 > self.audio_chunk_len {
-			// We would read outside of the chunk.
-			// In theory this is a format error as the packet table is not
-			// supposed to have such a length combination that the sum is larger
-			// than the size of the audio chunk + 4 for edit_count.
-			// But we are too lazy to return Result<...> here...
-			None
-		} else {
-			Some(res)
+		// Lengths from a crafted packet table might not fit
+		if res > usize::max_value() as u64 || res > i64::max_value() as u64 {
+			return None;
+		}
+		if self.audio_chunk_len != -1 {
+			match self.audio_chunk_offs.checked_add(res as i64) {
+				Some(end) if end <= self.audio_chunk_len => (),
+				// We would read outside of the chunk.
+				// In theory this is a format error as the packet table is not
+				// supposed to have such a length combination that the sum is larger
+				// than the size of the audio chunk + 4 for edit_count.
+				// But we are too lazy to return Result<...> here...
+				_ => return None,
+			}
+		}
+		Some(res as usize)
+	}
+	/// Returns whether the packets ended early because the audio chunk is too short
+	///
+	/// If the packet table describes more packets than fit into the
+	/// audio chunk, e.g. because the file got truncated, reading stops at
+	/// the end of the audio chunk, as if all packets had been read.
+	/// Once reading has stopped, this function tells these cases apart.
+	/// The number of packets that were available is then
+	/// given by `get_packet_idx`.
+	pub fn was_truncated(&self) -> bool {
+		if self.next_packet_size().is_some() {
+			return false;
+		}
+		match self.get_packet_count() {
+			Some(c) => self.packet_idx < c,
+			None => false,
 		}
 	}
 	/// Read one packet from the audio chunk
@@ -400,69 +1358,843 @@ impl<T> CafPacketReader<T> where T :Read + Seek {
 		};
 
 		let mut arr = vec![0; next_packet_size];
-		try!(self.ch_rdr.rdr.read_exact(&mut arr));
-		self.packet_idx += 1;
-		self.audio_chunk_offs += next_packet_size as i64;
+		if !try!(self.read_packet_data(&mut arr)) {
+			return Ok(None);
+		}
 		return Ok(Some(arr));
 	}
+	/// Reads the data of the next packet and advances to the packet after it
+	///
+	/// Returns `Ok(false)` if an audio data chunk of unspecified size
+	/// ends right before the packet. The size of the chunk is known
+	/// from then on, so no further packets are read.
+	fn read_packet_data(&mut self, data :&mut [u8]) -> Result<bool, CafError> {
+		if self.audio_chunk_len == -1 && data.len() > 0 {
+			let read = loop {
+				match self.ch_rdr.rdr.read(data) {
+					Ok(v) => break v,
+					Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+					Err(e) => return Err(CafError::Io(e)),
+				}
+			};
+			if read == 0 {
+				self.audio_chunk_len = self.audio_chunk_offs;
+				return Ok(false);
+			}
+			try!(self.ch_rdr.rdr.read_exact(&mut data[read..]));
+		} else {
+			try!(self.ch_rdr.rdr.read_exact(data));
+		}
+		self.packet_idx += 1;
+		self.audio_chunk_offs += data.len() as i64;
+		let packet_idx = self.packet_idx;
+		try!(self.load_packet_length(packet_idx));
+		Ok(true)
+	}
+	/// Returns an iterator over the remaining packets
+	///
+	/// The packets are read one by one from the underlying reader,
+	/// so the audio data chunk is never held in memory as a whole.
+	/// Unlike iterating over the reader directly,
+	/// the iterator ends after the first error.
+	pub fn into_packet_iter(self) -> PacketIter<T> {
+		PacketIter {
+			pkt_rdr : self,
+			done : false,
+		}
+	}
+	/// Read one packet from the audio chunk, together with its position
+	///
+	/// Like `next_packet`, but also returns the position of the
+	/// packet in the file, relative to the start of the underlying reader.
+	/// Useful for building seek indices.
+	pub fn next_packet_with_offset(&mut self) -> Result<Option<(u64, Vec<u8>)>, CafError> {
+		// The audio chunk offset includes the 4 bytes of the edit count
+		let offs = self.audio_data_start + (self.audio_chunk_offs - 4) as u64;
+		Ok(try!(self.next_packet()).map(|packet| (offs, packet)))
+	}
+	/// Read one packet from the audio chunk into an internal buffer
+	///
+	/// Like `next_packet`, but instead of allocating a new `Vec` for
+	/// each packet, the packet is read into a buffer owned by the reader,
+	/// and a slice of it is returned.
+	/// The buffer gets reused by the next invocation.
+	pub fn next_packet_ref(&mut self) -> Result<Option<&[u8]>, CafError> {
+		let next_packet_size = match self.next_packet_size() {
+			Some(v) => v,
+			None => return Ok(None),
+		};
+
+		let mut packet_buf = ::core::mem::replace(&mut self.packet_buf, Vec::new());
+		packet_buf.resize(next_packet_size, 0);
+		let res = self.read_packet_data(&mut packet_buf);
+		self.packet_buf = packet_buf;
+		if !try!(res) {
+			return Ok(None);
+		}
+		return Ok(Some(&self.packet_buf));
+	}
 	/// Read one packet from the audio chunk into a pre-allocated array
 	///
 	/// The method doesn't check whether the size of the passed slice matches
 	/// the actual next packet length, it uses the length blindly.
 	/// For correct operation, only use sizes returned from the
 	/// `next_packet_size` function, and only if it didn't return `None`.
+	///
+	/// If the audio data chunk has an unspecified size, `next_packet_size`
+	/// can't tell where the chunk ends. Reaching its end before the packet
+	/// gives an `UnexpectedEof` error, after which `next_packet_size`
+	/// returns `None`.
 	pub fn read_packet_into(&mut self, data :&mut [u8]) -> Result<(), CafError> {
-		try!(self.ch_rdr.rdr.read_exact(data));
-		self.packet_idx += 1;
-		self.audio_chunk_offs += data.len() as i64;
+		if !try!(self.read_packet_data(data)) {
+			return Err(CafError::Io(IoError::new(ErrorKind::UnexpectedEof,
+				"end of the audio data reached")));
+		}
 		return Ok(());
 	}
 
+	/// Returns the position in the file of the packet containing the given frame
+	///
+	/// Frames are counted from the first frame of the first packet.
+	/// The position is relative to the start of the underlying reader.
+	///
+	/// Returns `None` if the frame is outside of the audio data, or if
+	/// the position can't be determined, because the number of frames per
+	/// packet is unknown, or the packet table is read lazily.
+	pub fn byte_offset_of_frame(&self, frame :u64) -> Option<u64> {
+		let frames_per_packet = self.frames_per_packet() as u64;
+		if frames_per_packet == 0 {
+			return None;
+		}
+		let packet_idx = frame / frames_per_packet;
+		match self.get_packet_count() {
+			Some(c) if packet_idx < c as u64 => (),
+			_ => return None,
+		}
+		// The lengths might come from a crafted packet table
+		let offs = match (self.audio_desc.bytes_per_packet, &self.packet_table) {
+			(0, _) if self.lazy_lengths.is_some() => return None,
+			(0, &Some(ref t)) => t.lengths[..packet_idx as usize].iter()
+				.fold(Some(0u64), |s, v| s.and_then(|s| s.checked_add(*v))),
+			(0, &None) => return None,
+			(v, _) => packet_idx.checked_mul(v as u64),
+		};
+		offs.and_then(|offs| self.audio_data_start.checked_add(offs))
+	}
+
+	/// Returns the entries of all info chunks, merged
+	///
+	/// The info chunks are only available if `Info` was passed in
+	/// the `filter_by` list when creating the reader. A file may contain
+	/// multiple info chunks. If a key is present in multiple of them,
+	/// the value from the chunk that comes last in the file is kept.
+	/// If the file has no info chunk, the list is empty.
+	pub fn all_info(&self) -> Vec<(String, String)> {
+		let mut res = Vec::new();
+		for chunk in self.chunks.iter() {
+			if let &CafChunk::Info(ref entries) = chunk {
+				merge_info(&mut res, entries);
+			}
+		}
+		res
+	}
+
+	/// Returns the magic cookie, if the audio has the given format
+	///
+	/// The magic cookie is only available if `MagicCookie` was
+	/// passed in the `filter_by` list when creating the reader.
+	/// For Apple Lossless, pass it to `chunks::parse_alac_cookie`
+	/// to obtain the decoder configuration.
+	pub fn magic_cookie_for(&self, format :FormatType) -> Option<&[u8]> {
+		if self.audio_desc.format_id != format {
+			return None;
+		}
+		for chunk in self.chunks.iter() {
+			if let &CafChunk::MagicCookie(ref cookie) = chunk {
+				return Some(cookie);
+			}
+		}
+		None
+	}
+
+	/// Returns the ratio between the decoded and the stored size of the audio
+	///
+	/// The decoded size is computed from the number of frames, the number
+	/// of channels and the bit depth of the decoded samples.
+	/// A value of 3.2 means that the audio data is compressed 3.2:1.
+	///
+	/// Returns `None` if any of those are unknown, e.g. because
+	/// the bit depth isn't stored for the format, or the audio
+	/// data chunk has unspecified size.
+	pub fn compression_ratio(&self) -> Option<f64> {
+		let bits = match self.audio_desc.decoded_bits_per_channel() {
+			Some(v) => v,
+			None => return None,
+		};
+		let frames = match (&self.packet_table, self.get_packet_count()) {
+			(&Some(ref t), _) => t.num_valid_frames,
+			(&None, Some(c)) => c as i64 * self.frames_per_packet() as i64,
+			(&None, None) => return None,
+		};
+		if self.audio_chunk_len <= 4 || frames <= 0 {
+			return None;
+		}
+		let decoded_bits = frames as f64 *
+			self.audio_desc.channels_per_frame as f64 * bits as f64;
+		Some(decoded_bits / 8.0 / (self.audio_chunk_len - 4) as f64)
+	}
+
+	/// Encodes the file into a new in-memory CAF file
+	///
+	/// The result contains the audio description, the chunks
+	/// stored in `chunks`, the packet table and all packets of the audio
+	/// data chunk. It can be decoded by a fresh `CafPacketReader`.
+	///
+	/// For this, the reader rewinds to the first packet, and
+	/// reads all packets. Afterwards, the reader is positioned
+	/// after the last packet.
+	pub fn reencode(&mut self) -> Result<Vec<u8>, CafError> {
+		try!(self.reset());
+
+		let mut wtr = write::TwoPassWriter::new(Vec::new(), self.audio_desc.clone());
+		for chunk in self.chunks.iter() {
+			wtr.add_chunk(chunk.clone());
+		}
+		wtr.set_edit_count(self.edit_count);
+		if let Some(ref t) = self.packet_table {
+			wtr.set_frame_counts(t.num_valid_frames,
+				t.num_priming_frames, t.num_remainder_frames);
+		}
+		while let Some(packet) = try!(self.next_packet_ref()) {
+			wtr.write_packet(packet);
+		}
+		wtr.finish()
+	}
+
+	/// Returns a reader over the remaining bytes of the audio chunk
+	///
+	/// The reader is limited to the audio chunk, so it never reads
+	/// into any chunks following it. Chunks with unspecified size
+	/// are read up to the EOF.
+	///
+	/// This is useful if you want to pass the audio to a decoder
+	/// that doesn't care about packet boundaries.
+	/// Note that the packet based functions don't know about the bytes
+	/// read through the returned reader, so don't mix them.
+	pub fn audio_reader<'a>(&'a mut self) -> AudioReader<'a, T> {
+		AudioReader { pkt_rdr : self }
+	}
+
+	/// Reads raw bytes of the audio chunk, ignoring packet boundaries
+	///
+	/// Works like `Read::read` on the reader returned by `audio_reader`:
+	/// it never reads past the end of the audio chunk, and returns 0 once
+	/// that end is reached. The same caveat applies as well: don't mix this
+	/// with the packet based functions.
+	pub fn read_raw(&mut self, buf :&mut [u8]) -> Result<usize, CafError> {
+		Ok(try!(self.audio_reader().read(buf)))
+	}
+
+	/// Returns an iterator over the interleaved PCM samples
+	///
+	/// Linear PCM samples are converted to `i16`, and µ-law and A-law
+	/// samples are decoded. For all other formats,
+	/// `CafError::UnsupportedFormat` is returned, as they need
+	/// a full decoder.
+	pub fn pcm_samples_iter(self) -> Result<pcm::PcmStream<T>, CafError> {
+		pcm::PcmStream::from_packet_reader(self)
+	}
+
+	/// Returns the number of samples a full decode via `pcm_samples_iter` yields
+	///
+	/// That is the number of frames times the number of channels,
+	/// counted from the first packet, regardless of the current position.
+	/// Useful for allocating the output buffer upfront.
+	/// Returns `None` for compressed formats, or if the
+	/// number of packets is unknown.
+	pub fn pcm_output_len(&self) -> Option<usize> {
+		match self.audio_desc.format_id {
+			FormatType::LinearPcm | FormatType::Ulaw | FormatType::Alaw => (),
+			_ => return None,
+		}
+		if self.audio_desc.frames_per_packet != 1 {
+			return None;
+		}
+		match self.get_packet_count() {
+			Some(c) => c.checked_mul(self.audio_desc.channels_per_frame as usize),
+			None => None,
+		}
+	}
+
+	/// Returns the edit count stored in the audio data chunk
+	///
+	/// The first four bytes of the audio data chunk hold the edit count,
+	/// not audio data. It is changed whenever the audio data is edited,
+	/// so that dependent chunks like the overview can be recognized
+	/// as outdated. Same as the `edit_count` member.
+	pub fn edit_count(&self) -> u32 {
+		self.edit_count
+	}
+
+	/// Returns the position of the first packet in the file
+	///
+	/// This is the position right after the edit count of the audio data
+	/// chunk, relative to the start of the underlying reader.
+	pub fn audio_data_offset(&self) -> u64 {
+		self.audio_data_start
+	}
+
+	/// Returns the size of the audio data chunk as given in its header
+	///
+	/// The size includes the 4 bytes of the edit count.
+	/// -1 means that the size is unspecified, and the
+	/// audio data chunk extends to the end of the file.
+	/// Once reading the packets reached the end of such a chunk,
+	/// its actual size is returned instead.
+	pub fn audio_data_len(&self) -> i64 {
+		self.audio_chunk_len
+	}
+
 	/// Gets the number of packets if its known.
 	pub fn get_packet_count(&self) -> Option<usize> {
 		match &self.packet_table {
-			&Some(ref t) => Some(t.lengths.len()),
+			&Some(ref t) => Some(match self.lazy_lengths {
+				Some(ref l) => l.num_packets,
+				None => t.lengths.len(),
+			}),
 			&None => match self.audio_desc.bytes_per_packet {
-				// We are supposed to never reach this as the constructor
-				// should enforce a packet table to be present if the
-				// number of bytes per packet is unspecified.
-				0 => panic!("No packet table was stored by the constructor"),
+				// The constructors enforce a packet table to be present
+				// if the number of bytes per packet is unspecified,
+				// but the public field might have been changed since.
+				0 => None,
 				// If the length of the audio chunk is unspecified,
 				// and there is no packet table,
 				// we won't know the count of packets.
 				_ if self.audio_chunk_len == -1 => None,
+				// Rejected by the constructors, but the chunk
+				// needs to be large enough to hold the edit count.
+				_ if self.audio_chunk_len < 4 => None,
 				v => Some((self.audio_chunk_len as usize - 4) / v as usize),
 			},
 		}
 	}
 
+	/// Returns the number of packets that haven't been read yet
+	///
+	/// Returns `None` if the number of packets is unknown,
+	/// see `get_packet_count`.
+	pub fn packets_remaining(&self) -> Option<usize> {
+		self.get_packet_count().map(|c| c.saturating_sub(self.packet_idx))
+	}
+
+	/// Returns the index of the first frame of the next packet
+	///
+	/// Frames are counted from the first frame of the first packet,
+	/// so priming frames are included.
+	/// Returns `None` if the number of frames per packet is zero, i.e.
+	/// varies between packets. The packet table only stores the packet
+	/// sizes in bytes, so the frame position can't be computed then.
+	pub fn current_frame(&self) -> Option<u64> {
+		match self.frames_per_packet() {
+			0 => None,
+			v => Some(self.packet_idx as u64 * v as u64),
+		}
+	}
+
 	/// Returns the index of the currently read packet
 	pub fn get_packet_idx(&self) -> usize {
 		self.packet_idx
 	}
 
+	/// Returns the duration of all packets in seconds
+	///
+	/// This includes the priming and remainder frames, so for formats like
+	/// AAC it is longer than what a player should present to the user.
+	/// Use `playable_duration_seconds` for that.
+	///
+	/// Returns `None` if the number of frames is unknown.
+	pub fn duration_seconds(&self) -> Option<f64> {
+		let frames = match (self.frames_per_packet(), &self.packet_table) {
+			(0, &Some(ref t)) => t.num_valid_frames +
+				t.num_priming_frames as i64 + t.num_remainder_frames as i64,
+			(0, &None) => return None,
+			(v, _) => match self.get_packet_count() {
+				Some(c) => c as i64 * v as i64,
+				None => return None,
+			},
+		};
+		self.frames_to_seconds(frames)
+	}
+
+	/// Returns the duration of the audio in seconds, as it should be presented
+	///
+	/// Unlike `duration_seconds`, this only counts the valid frames
+	/// stored in the packet table, so the priming and remainder frames
+	/// which gapless playback trims away are excluded.
+	/// If there is no packet table, there are no such frames,
+	/// and the result is the same as the one of `duration_seconds`.
+	pub fn playable_duration_seconds(&self) -> Option<f64> {
+		match self.packet_table {
+			Some(ref t) => self.frames_to_seconds(t.num_valid_frames),
+			None => self.duration_seconds(),
+		}
+	}
+
+	/// Returns the playback duration
+	///
+	/// The same as `playable_duration_seconds`, as a `Duration`:
+	/// the priming and remainder frames are not included.
+	pub fn duration(&self) -> Option<Duration> {
+		let secs = match self.playable_duration_seconds() {
+			Some(v) if v >= 0.0 && v < u64::max_value() as f64 => v,
+			_ => return None,
+		};
+		let nanos = ((secs - math::floor(secs)) * 1_000_000_000.0) as u32;
+		Some(Duration::new(math::floor(secs) as u64, ::core::cmp::min(nanos, 999_999_999)))
+	}
+
+	fn frames_to_seconds(&self, frames :i64) -> Option<f64> {
+		let sample_rate = self.audio_desc.sample_rate;
+		if !(sample_rate > 0.0) {
+			return None;
+		}
+		Some(frames as f64 / sample_rate)
+	}
+
+	/// Returns the range of packets covering the given time range
+	///
+	/// `start` and `end` are in seconds, and the range is `[start, end)`.
+	/// Times are mapped to frames via the sample rate, and frames
+	/// to packets via the number of frames per packet, counting from
+	/// the first frame of the first packet.
+	/// The result is clamped to the packets present in the file.
+	///
+	/// Returns `None` if the range lies outside of the file, or if
+	/// the mapping can't be computed because the number of frames per
+	/// packet or the number of packets is unknown.
+	pub fn packet_range_for_time(&self, start :f64, end :f64) -> Option<Range<usize>> {
+		let frames_per_packet = self.frames_per_packet() as f64;
+		let sample_rate = self.audio_desc.sample_rate;
+		if frames_per_packet == 0.0 || !(sample_rate > 0.0) || !(start < end) {
+			return None;
+		}
+		let packet_count = match self.get_packet_count() {
+			Some(v) => v,
+			None => return None,
+		};
+		let start_frame = math::floor(start.max(0.0) * sample_rate);
+		let end_frame = math::ceil(end * sample_rate);
+		let start_idx = math::floor(start_frame / frames_per_packet) as usize;
+		let end_idx = ::core::cmp::min(
+			math::ceil(end_frame / frames_per_packet) as usize, packet_count);
+		if start_idx >= end_idx {
+			return None;
+		}
+		Some(start_idx .. end_idx)
+	}
+
+	/// Rewinds to the first packet
+	///
+	/// Afterwards, the first packet is the next one to be read,
+	/// e.g. for playing the audio again.
+	pub fn reset(&mut self) -> Result<(), CafError> {
+		try!(self.ch_rdr.rdr.seek(SeekFrom::Start(self.audio_data_start)));
+		self.audio_chunk_offs = 4; // 4 bytes for the edit count.
+		self.packet_idx = 0;
+		try!(self.load_packet_length(0));
+		Ok(())
+	}
+
 	/// Seeks to the packet with the given index
 	///
-	/// This function never has been tested.
-	/// If there are bugs please report them.
+	/// Afterwards, the packet with the given index is the next one
+	/// to be read. Seeking to the packet count is allowed, and
+	/// puts the reader after the last packet.
+	/// If the index is larger than the packet count,
+	/// an `InvalidInput` io error is returned.
 	pub fn seek_to_packet(&mut self, packet_idx :usize) -> Result<(), CafError> {
+		match self.get_packet_count() {
+			Some(c) if packet_idx > c => {
+				return Err(CafError::Io(IoError::new(ErrorKind::InvalidInput,
+					"packet index out of range")));
+			},
+			_ => (),
+		}
+
+		let min_idx = ::core::cmp::min(self.packet_idx, packet_idx);
+		let max_idx = ::core::cmp::max(self.packet_idx, packet_idx);
 
-		let min_idx = ::std::cmp::min(self.packet_idx, packet_idx);
-		let max_idx = ::std::cmp::min(self.packet_idx, packet_idx);
+		// The lengths might come from a crafted packet table
+		let too_far = || Err(CafError::Io(IoError::new(ErrorKind::InvalidData,
+			"packet lengths exceed the maximum seek distance")));
 
 		// The amount we need to seek by.
-		let offs :i64 = match self.audio_desc.bytes_per_packet {
-			0 => self.packet_table.as_ref()
-				.unwrap().lengths[min_idx..max_idx].iter().map(|v| *v as i64).sum(),
-			v => (max_idx - min_idx) as i64 * v as i64,
+		let offs :u64 = match self.audio_desc.bytes_per_packet {
+			0 => {
+				let mut offs :u64 = 0;
+				for idx in min_idx .. max_idx {
+					try!(self.load_packet_length(idx));
+					// Only fails if the packet table has been removed
+					let len = match self.packet_length(idx) {
+						Some(len) => len,
+						None => return Err(CafError::Io(IoError::new(
+							ErrorKind::InvalidData, "packet length unknown"))),
+					};
+					offs = match offs.checked_add(len) {
+						Some(v) => v,
+						None => return too_far(),
+					};
+				}
+				offs
+			},
+			v => match ((max_idx - min_idx) as u64).checked_mul(v as u64) {
+				Some(v) => v,
+				None => return too_far(),
+			},
 		};
-		if self.packet_idx < packet_idx {
-			try!(self.ch_rdr.rdr.seek(SeekFrom::Current(offs)));
-		} else if self.packet_idx > packet_idx {
-			try!(self.ch_rdr.rdr.seek(SeekFrom::Current(-offs)));
-		} else {
-			// No seek needed
+		if offs > i64::max_value() as u64 {
+			return too_far();
 		}
+		let offs = if self.packet_idx < packet_idx { offs as i64 } else { -(offs as i64) };
+		let audio_chunk_offs = match self.audio_chunk_offs.checked_add(offs) {
+			Some(v) => v,
+			None => return too_far(),
+		};
+		try!(self.ch_rdr.rdr.seek(SeekFrom::Current(offs)));
+		self.audio_chunk_offs = audio_chunk_offs;
+		self.packet_idx = packet_idx;
+		try!(self.load_packet_length(packet_idx));
 		Ok(())
 	}
+
+	/// Seeks to the packet containing the given frame
+	///
+	/// Frames are counted like in `current_frame`. Returns the index of
+	/// the first frame of the packet that was sought to, which is the given
+	/// frame rounded down to a packet boundary.
+	/// Frames beyond the last packet put the reader after the last packet.
+	///
+	/// If the number of frames per packet is zero, the frame can't be
+	/// mapped to a packet, and an `InvalidInput` io error is returned.
+	pub fn seek_to_frame(&mut self, frame :u64) -> Result<u64, CafError> {
+		let frames_per_packet = match self.frames_per_packet() {
+			0 => return Err(CafError::Io(IoError::new(ErrorKind::InvalidInput,
+				"can't map frames to packets with variable frames per packet"))),
+			v => v as u64,
+		};
+		let mut packet_idx = frame / frames_per_packet;
+		if let Some(c) = self.get_packet_count() {
+			packet_idx = ::core::cmp::min(packet_idx, c as u64);
+		}
+		try!(self.seek_to_packet(packet_idx as usize));
+		Ok(packet_idx * frames_per_packet)
+	}
+
+	/// Seeks to the packet containing the given byte of the audio data
+	///
+	/// `payload_offset` is relative to the first packet. The position gets
+	/// rounded down to the start of the packet containing that byte.
+	/// Offsets beyond the last packet put the reader after the last packet.
+	/// Fails for variable packet sizes if `packet_table` has been removed.
+	pub fn seek_to_byte(&mut self, payload_offset :u64) -> Result<(), CafError> {
+		let packet_count = self.get_packet_count();
+		let packet_idx = match self.audio_desc.bytes_per_packet {
+			0 => {
+				let unknown_len = || Err(CafError::Io(IoError::new(
+					ErrorKind::InvalidData, "packet length unknown")));
+				// Only happens if the packet table has been removed
+				let packet_count = match packet_count {
+					Some(c) => c,
+					None => return unknown_len(),
+				};
+				let mut offs = 0u64;
+				let mut idx = 0;
+				while idx < packet_count {
+					try!(self.load_packet_length(idx));
+					let len = match self.packet_length(idx) {
+						Some(len) => len,
+						None => return unknown_len(),
+					};
+					// An overflow puts the packet's end past any offset
+					offs = match offs.checked_add(len) {
+						Some(v) if v <= payload_offset => v,
+						_ => break,
+					};
+					idx += 1;
+				}
+				idx
+			},
+			v => {
+				let idx = payload_offset / v as u64;
+				match packet_count {
+					Some(c) if idx > c as u64 => c,
+					_ => idx as usize,
+				}
+			},
+		};
+		self.seek_to_packet(packet_idx)
+	}
+}
+
+/**
+Builder for a `CafPacketReader`
+
+Alternative to passing the `filter_by` list to `CafPacketReader::new`.
+The chunks kept by the builder are available in the `chunks` member
+of the created reader.
+Unlike with `filter_by`, the kept chunks are optional: if the file
+doesn't contain one of them, it is simply missing from `chunks`.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct CafPacketReaderBuilder {
+	keep :Vec<ChunkType>,
+	lazy :bool,
+}
+
+impl CafPacketReaderBuilder {
+	pub fn new() -> Self {
+		CafPacketReaderBuilder::default()
+	}
+	/// Keeps the chunks of the given type
+	///
+	/// The audio data, audio description and packet table chunks are
+	/// always retrieved by the reader, and thus ignored here.
+	pub fn keep_chunk(mut self, ch_type :ChunkType) -> Self {
+		match ch_type {
+			ChunkType::AudioData |
+			ChunkType::AudioDescription |
+			ChunkType::PacketTable => (),
+			_ if self.keep.contains(&ch_type) => (),
+			_ => self.keep.push(ch_type),
+		}
+		self
+	}
+	/// Keeps the channel layout chunk
+	pub fn keep_channel_layout(self) -> Self {
+		self.keep_chunk(ChunkType::ChannelLayout)
+	}
+	/// Keeps the magic cookie chunk
+	pub fn keep_magic_cookie(self) -> Self {
+		self.keep_chunk(ChunkType::MagicCookie)
+	}
+	/// Reads the packet table lazily, like `CafPacketReader::new_lazy` does
+	pub fn lazy_packet_table(mut self) -> Self {
+		self.lazy = true;
+		self
+	}
+	/// Creates the packet reader, like `CafPacketReader::new` does
+	pub fn build<T>(self, rdr :T) -> Result<CafPacketReader<T>, CafError>
+			where T :Read + Seek {
+		let ch_rdr = try!(CafChunkReader::new(rdr));
+		CafPacketReader::from_chunk_reader_inner(ch_rdr, Vec::new(),
+			self.keep, self.lazy)
+	}
+}
+
+impl<'a> CafPacketReader<Cursor<&'a [u8]>> {
+	/// Creates a packet reader for a file that is already in memory
+	///
+	/// Equal to calling `new` with the slice wrapped into a `Cursor`.
+	pub fn from_bytes(data :&'a [u8], filter_by :Vec<ChunkType>) -> Result<Self, CafError> {
+		CafPacketReader::new(Cursor::new(data), filter_by)
+	}
+}
+
+impl<T> CafPacketReader<SequentialReader<T>> where T :Read {
+	/// Creates a packet reader for a reader that doesn't support seeking
+	///
+	/// As the reader can only move forward, the audio description
+	/// and, if the format requires one, the packet table chunk need to
+	/// precede the audio data chunk. Otherwise, `CafError::MissingChunk`
+	/// is returned once the audio data chunk is reached.
+	/// The packet table is read to memory, like with `new`.
+	///
+	/// Only chunks listed in `filter_by` that precede the audio data chunk
+	/// are available in `chunks`. Functions that need to seek backwards,
+	/// like `seek_to_packet` with an index before the current one,
+	/// return an error.
+	pub fn new_sequential(rdr :T, filter_by :Vec<ChunkType>) -> Result<Self, CafError> {
+		let mut ch_rdr = try!(CafChunkReader::new(SequentialReader::new(rdr)));
+		let mut audio_desc = None;
+		let mut packet_table = None;
+		let mut chunks = Vec::new();
+		let audio_chunk_len;
+		loop {
+			let hdr = try!(ch_rdr.read_chunk_header());
+			match hdr.ch_type {
+				ChunkType::AudioData => {
+					try!(check_audio_chunk_len(hdr.ch_size));
+					audio_chunk_len = hdr.ch_size;
+					break;
+				},
+				ChunkType::AudioDescription => match try!(ch_rdr.read_chunk_body(&hdr)) {
+					CafChunk::Desc(v) => audio_desc = Some(v),
+					_ => unreachable!(),
+				},
+				ChunkType::PacketTable => match try!(ch_rdr.read_chunk_body(&hdr)) {
+					CafChunk::PacketTable(v) => packet_table = Some(v),
+					_ => unreachable!(),
+				},
+				t if filter_by.contains(&t) => chunks.push(try!(ch_rdr.read_chunk_body(&hdr))),
+				_ => try!(ch_rdr.try_to_next_chunk(&hdr)),
+			}
+		}
+		let audio_desc = match audio_desc {
+			Some(v) => v,
+			None => return Err(CafError::MissingChunk(ChunkType::AudioDescription)),
+		};
+		let p_table_required = audio_desc.bytes_per_packet == 0 ||
+			audio_desc.frames_per_packet == 0;
+		if p_table_required && packet_table.is_none() {
+			return Err(CafError::MissingChunk(ChunkType::PacketTable));
+		}
+		let edit_count = try!(ch_rdr.rdr.read_u32::<Be>());
+		let audio_data_start = ch_rdr.rdr.pos;
+		Ok(CafPacketReader {
+			ch_rdr : ch_rdr,
+			audio_desc : audio_desc,
+			packet_table : packet_table,
+			chunks : chunks,
+			edit_count : edit_count,
+			audio_chunk_len : audio_chunk_len,
+			audio_chunk_offs : 4, // 4 bytes for the edit count.
+			audio_data_start : audio_data_start,
+			packet_idx : 0,
+			packet_buf : Vec::new(),
+			lazy_lengths : None,
+			frames_per_packet_override : None,
+		})
+	}
+}
+
+/**
+Wrapper for readers that don't support seeking
+
+Used by `CafPacketReader::new_sequential`.
+It implements `Seek` by keeping track of the position:
+seeking forward skips the bytes by reading them,
+seeking backwards or relative to the end returns an error.
+*/
+pub struct SequentialReader<T> where T :Read {
+	rdr :T,
+	pos :u64,
+}
+
+impl<T> SequentialReader<T> where T :Read {
+	pub fn new(rdr :T) -> Self {
+		SequentialReader {
+			rdr : rdr,
+			pos : 0,
+		}
+	}
+	/// Returns the wrapped reader
+	pub fn into_inner(self) -> T {
+		self.rdr
+	}
+}
+
+impl<T> Read for SequentialReader<T> where T :Read {
+	fn read(&mut self, buf :&mut [u8]) -> io::Result<usize> {
+		let read = try!(self.rdr.read(buf));
+		self.pos += read as u64;
+		Ok(read)
+	}
+}
+
+impl<T> Seek for SequentialReader<T> where T :Read {
+	fn seek(&mut self, pos :SeekFrom) -> io::Result<u64> {
+		let target = match pos {
+			SeekFrom::Start(v) => Some(v),
+			SeekFrom::Current(v) if v >= 0 => Some(self.pos + v as u64),
+			SeekFrom::Current(v) => self.pos.checked_sub(v.wrapping_neg() as u64),
+			SeekFrom::End(_) => return Err(IoError::new(ErrorKind::Other,
+				"can't seek relative to the end of a sequential stream")),
+		};
+		let target = match target {
+			Some(v) if v >= self.pos => v,
+			_ => return Err(IoError::new(ErrorKind::Other,
+				"can't seek backwards in a sequential stream")),
+		};
+		let to_skip = target - self.pos;
+		let skipped = try!(io::copy(&mut (&mut self.rdr).take(to_skip),
+			&mut io::sink()));
+		self.pos += skipped;
+		if skipped < to_skip {
+			return Err(IoError::new(ErrorKind::UnexpectedEof,
+				"EOF reached while seeking"));
+		}
+		Ok(self.pos)
+	}
+}
+
+/// Reader over the audio chunk, returned by `CafPacketReader::audio_reader`
+pub struct AudioReader<'a, T> where T :Read + Seek + 'a {
+	pkt_rdr :&'a mut CafPacketReader<T>,
+}
+
+impl<'a, T> Read for AudioReader<'a, T> where T :Read + Seek + 'a {
+	fn read(&mut self, buf :&mut [u8]) -> io::Result<usize> {
+		let rdr = &mut self.pkt_rdr;
+		let len = if rdr.audio_chunk_len == -1 {
+			buf.len()
+		} else {
+			let remaining = rdr.audio_chunk_len - rdr.audio_chunk_offs;
+			::core::cmp::max(0, ::core::cmp::min(buf.len() as i64, remaining)) as usize
+		};
+		let read = try!(rdr.ch_rdr.rdr.read(&mut buf[..len]));
+		rdr.audio_chunk_offs += read as i64;
+		Ok(read)
+	}
+}
+
+impl<T> PacketSource for CafPacketReader<T> where T :Read + Seek {
+	fn next_packet(&mut self) -> Result<Option<Vec<u8>>, CafError> {
+		CafPacketReader::next_packet(self)
+	}
+	fn audio_description(&self) -> &chunks::AudioDescription {
+		&self.audio_desc
+	}
+}
+
+impl<T> Iterator for CafPacketReader<T> where T :Read + Seek {
+	type Item = Result<Vec<u8>, CafError>;
+	/// Returns the next packet, like `next_packet` does
+	///
+	/// Errors are returned as `Some(Err(..))`. As the failed packet
+	/// isn't skipped, you should stop iterating after an error.
+	fn next(&mut self) -> Option<Self::Item> {
+		match CafPacketReader::next_packet(self) {
+			Ok(Some(packet)) => Some(Ok(packet)),
+			Ok(None) => None,
+			Err(e) => Some(Err(e)),
+		}
+	}
+}
+
+/// Iterator over the packets, returned by `CafPacketReader::into_packet_iter`
+///
+/// Ends once all packets were read, or after the first error.
+pub struct PacketIter<T> where T :Read + Seek {
+	pkt_rdr :CafPacketReader<T>,
+	done :bool,
+}
+
+impl<T> Iterator for PacketIter<T> where T :Read + Seek {
+	type Item = Result<Vec<u8>, CafError>;
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+		match self.pkt_rdr.next_packet() {
+			Ok(Some(packet)) => Some(Ok(packet)),
+			Ok(None) => {
+				self.done = true;
+				None
+			},
+			Err(e) => {
+				self.done = true;
+				Some(Err(e))
+			},
+		}
+	}
 }
@@ -0,0 +1,62 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+/*!
+Rounding of floats
+
+The rounding methods of `f64` need `std`,
+so without it they are implemented here.
+*/
+
+#[cfg(feature = "std")]
+pub fn floor(v :f64) -> f64 {
+	v.floor()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn floor(v :f64) -> f64 {
+	// Floats this large have no fractional part. Also covers NaN.
+	if !(v > -4503599627370496.0 && v < 4503599627370496.0) {
+		return v;
+	}
+	let truncated = v as i64 as f64;
+	if truncated > v {
+		truncated - 1.0
+	} else {
+		truncated
+	}
+}
+
+#[cfg(feature = "std")]
+pub fn ceil(v :f64) -> f64 {
+	v.ceil()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn ceil(v :f64) -> f64 {
+	-floor(-v)
+}
+
+#[cfg(feature = "std")]
+pub fn round(v :f64) -> f64 {
+	v.round()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn round(v :f64) -> f64 {
+	// Halfway cases are rounded away from zero, like `f64::round` does
+	if v < 0.0 {
+		return -round(-v);
+	}
+	let floored = floor(v);
+	if v - floored >= 0.5 {
+		floored + 1.0
+	} else {
+		floored
+	}
+}
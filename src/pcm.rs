@@ -0,0 +1,265 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+/*!
+Decoding of PCM audio to samples
+*/
+
+use io::{Read, Seek};
+use alloc::vec::Vec;
+use byteorder::{BigEndian, LittleEndian, ByteOrder};
+use ::{CafError, CafPacketReader, FormatType, SampleSpec};
+use chunks::AudioDescription;
+use spec::{FLAG_IS_FLOAT, FLAG_IS_LITTLE_ENDIAN, FLAG_IS_ALIGNED_HIGH};
+
+/// Decodes a µ-law encoded sample
+pub fn ulaw_to_i16(v :u8) -> i16 {
+	let v = !v;
+	let exponent = (v >> 4) & 0x07;
+	let mantissa = (v & 0x0f) as i16;
+	// The bias of 0x84 makes the segments line up
+	let magnitude = (((mantissa << 3) + 0x84) << exponent) - 0x84;
+	if v & 0x80 != 0 {
+		-magnitude
+	} else {
+		magnitude
+	}
+}
+
+/// Decodes an A-law encoded sample
+pub fn alaw_to_i16(v :u8) -> i16 {
+	let v = v ^ 0x55;
+	let exponent = (v >> 4) & 0x07;
+	let mantissa = (v & 0x0f) as i16;
+	let magnitude = match exponent {
+		0 => (mantissa << 4) + 8,
+		e => ((mantissa << 4) + 0x108) << (e - 1),
+	};
+	if v & 0x80 != 0 {
+		magnitude
+	} else {
+		-magnitude
+	}
+}
+
+/// How the samples are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SampleEncoding {
+	Int,
+	Float,
+	Ulaw,
+	Alaw,
+}
+
+/// How a single sample is stored
+#[derive(Debug, Clone, Copy)]
+struct SampleLayout {
+	/// The size of the sample's container in bytes
+	bytes :usize,
+	/// The number of significant bits
+	bits :u32,
+	encoding :SampleEncoding,
+	little_endian :bool,
+	aligned_high :bool,
+}
+
+impl SampleLayout {
+	fn from_desc(desc :&AudioDescription) -> Result<Self, CafError> {
+		let unsupported = Err(CafError::UnsupportedFormat(desc.format_id));
+		if desc.frames_per_packet != 1 || desc.channels_per_frame == 0 {
+			return unsupported;
+		}
+		let bytes = (desc.bytes_per_packet / desc.channels_per_frame) as usize;
+		let bits = desc.bits_per_channel;
+		let encoding = match desc.format_id {
+			FormatType::LinearPcm if desc.format_flags & FLAG_IS_FLOAT != 0 =>
+				SampleEncoding::Float,
+			FormatType::LinearPcm => SampleEncoding::Int,
+			FormatType::Ulaw => SampleEncoding::Ulaw,
+			FormatType::Alaw => SampleEncoding::Alaw,
+			_ => return unsupported,
+		};
+		let supported = match encoding {
+			SampleEncoding::Float =>
+				bits as usize == bytes * 8 && (bits == 32 || bits == 64),
+			SampleEncoding::Int =>
+				bits >= 8 && bits as usize <= bytes * 8 && bytes <= 4,
+			SampleEncoding::Ulaw | SampleEncoding::Alaw => bytes == 1,
+		};
+		if !supported {
+			return unsupported;
+		}
+		Ok(SampleLayout {
+			bytes : bytes,
+			bits : bits,
+			encoding : encoding,
+			little_endian : desc.format_flags & FLAG_IS_LITTLE_ENDIAN != 0,
+			aligned_high : desc.format_flags & FLAG_IS_ALIGNED_HIGH != 0,
+		})
+	}
+	/// Reads an integer sample, sign extended from its significant bits
+	fn read_int(&self, buf :&[u8]) -> i64 {
+		let v = if self.little_endian {
+			LittleEndian::read_int(buf, self.bytes)
+		} else {
+			BigEndian::read_int(buf, self.bytes)
+		};
+		let unused_bits = self.bytes as u32 * 8 - self.bits;
+		if self.aligned_high {
+			v >> unused_bits
+		} else {
+			// Sign extend from the significant bits
+			(v << (64 - self.bits)) >> (64 - self.bits)
+		}
+	}
+	fn read_float(&self, buf :&[u8]) -> f64 {
+		match (self.bytes, self.little_endian) {
+			(4, true) => LittleEndian::read_f32(buf) as f64,
+			(4, false) => BigEndian::read_f32(buf) as f64,
+			(_, true) => LittleEndian::read_f64(buf),
+			(_, false) => BigEndian::read_f64(buf),
+		}
+	}
+	fn read_i16(&self, buf :&[u8]) -> i16 {
+		match self.encoding {
+			SampleEncoding::Float => {
+				let v = self.read_float(buf) * 32768.0;
+				v.max(-32768.0).min(32767.0) as i16
+			},
+			SampleEncoding::Int if self.bits >= 16 =>
+				(self.read_int(buf) >> (self.bits - 16)) as i16,
+			SampleEncoding::Int =>
+				(self.read_int(buf) << (16 - self.bits)) as i16,
+			SampleEncoding::Ulaw => ulaw_to_i16(buf[0]),
+			SampleEncoding::Alaw => alaw_to_i16(buf[0]),
+		}
+	}
+	fn read_f32(&self, buf :&[u8]) -> f32 {
+		match self.encoding {
+			SampleEncoding::Float => self.read_float(buf) as f32,
+			SampleEncoding::Int =>
+				self.read_int(buf) as f32 / (1u64 << (self.bits - 1)) as f32,
+			SampleEncoding::Ulaw | SampleEncoding::Alaw =>
+				self.read_i16(buf) as f32 / 32768.0,
+		}
+	}
+}
+
+/**
+Decodes a packet of interleaved PCM audio to `i16` samples
+
+Supports the same formats as `open_pcm`, and converts the
+samples the same way `PcmStream` does.
+For other formats, `CafError::UnsupportedFormat` is returned.
+Trailing bytes that don't form a whole sample are ignored.
+*/
+pub fn decode_packet_i16(desc :&AudioDescription, packet :&[u8])
+		-> Result<Vec<i16>, CafError> {
+	let layout = try!(SampleLayout::from_desc(desc));
+	Ok(packet.chunks(layout.bytes)
+		.take(packet.len() / layout.bytes)
+		.map(|buf| layout.read_i16(buf))
+		.collect())
+}
+
+/**
+Decodes a packet of interleaved PCM audio to `f32` samples
+
+Like `decode_packet_i16`, but the samples are scaled to the
+range from -1.0 to 1.0. Float samples are passed through
+without clamping.
+*/
+pub fn decode_packet_f32(desc :&AudioDescription, packet :&[u8])
+		-> Result<Vec<f32>, CafError> {
+	let layout = try!(SampleLayout::from_desc(desc));
+	Ok(packet.chunks(layout.bytes)
+		.take(packet.len() / layout.bytes)
+		.map(|buf| layout.read_f32(buf))
+		.collect())
+}
+
+/**
+Opens a CAF file for reading its interleaved PCM samples
+
+Only works for Linear PCM files with integer samples
+of up to 32 bits, or float samples of 32 or 64 bits,
+and for µ-law and A-law files.
+For compressed formats, `CafError::UnsupportedFormat` is returned;
+use the `CafPacketReader` to obtain the packets and
+decode them with a decoder for the format.
+*/
+pub fn open_pcm<T>(rdr :T) -> Result<PcmStream<T>, CafError> where T :Read + Seek {
+	let pkt_rdr = try!(CafPacketReader::new(rdr, Vec::new()));
+	PcmStream::from_packet_reader(pkt_rdr)
+}
+
+/**
+Iterator over the interleaved samples of a PCM CAF file
+
+Samples are converted to `i16`: samples with more bits are
+truncated to their upper 16 bits, float samples are scaled and
+clamped to the `i16` range, µ-law and A-law samples are decoded.
+*/
+pub struct PcmStream<T> where T :Read + Seek {
+	/// The properties of the stream, with the format
+	/// being the one stored in the file.
+	pub spec :SampleSpec,
+	pkt_rdr :CafPacketReader<T>,
+	layout :SampleLayout,
+	packet :Vec<u8>,
+	packet_offs :usize,
+	done :bool,
+}
+
+impl<T> PcmStream<T> where T :Read + Seek {
+	/// Creates a sample iterator from a packet reader
+	///
+	/// Starts with the packet the reader is currently at.
+	pub fn from_packet_reader(pkt_rdr :CafPacketReader<T>) -> Result<Self, CafError> {
+		let layout = try!(SampleLayout::from_desc(&pkt_rdr.audio_desc));
+		Ok(PcmStream {
+			spec : SampleSpec::from(&pkt_rdr.audio_desc),
+			pkt_rdr : pkt_rdr,
+			layout : layout,
+			packet : Vec::new(),
+			packet_offs : 0,
+			done : false,
+		})
+	}
+	/// Returns the wrapped packet reader
+	pub fn into_inner(self) -> CafPacketReader<T> {
+		self.pkt_rdr
+	}
+}
+
+impl<T> Iterator for PcmStream<T> where T :Read + Seek {
+	type Item = Result<i16, CafError>;
+	fn next(&mut self) -> Option<Self::Item> {
+		while self.packet_offs + self.layout.bytes > self.packet.len() {
+			if self.done {
+				return None;
+			}
+			self.packet.clear();
+			self.packet_offs = 0;
+			match self.pkt_rdr.next_packet_ref() {
+				Ok(Some(packet)) => self.packet.extend_from_slice(packet),
+				Ok(None) => {
+					self.done = true;
+					return None;
+				},
+				Err(e) => {
+					self.done = true;
+					return Some(Err(e));
+				},
+			}
+		}
+		let offs = self.packet_offs;
+		self.packet_offs += self.layout.bytes;
+		Some(Ok(self.layout.read_i16(&self.packet[offs..])))
+	}
+}
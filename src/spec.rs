@@ -0,0 +1,131 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+/*!
+Format neutral description of audio samples
+
+Meant for bridging to other audio libraries.
+*/
+
+use ::FormatType;
+use chunks::AudioDescription;
+use math;
+
+/// The `format_flags` bit for float samples, for Linear PCM
+pub const FLAG_IS_FLOAT :u32 = 1;
+/// The `format_flags` bit for little endian samples, for Linear PCM
+pub const FLAG_IS_LITTLE_ENDIAN :u32 = 2;
+/// The `format_flags` bit for samples that are placed in the high bits
+/// of their container if they don't fill it, for Linear PCM
+pub const FLAG_IS_ALIGNED_HIGH :u32 = 16;
+
+/// The format of a single sample
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+	I8,
+	I16,
+	I24,
+	I32,
+	F32,
+	F64,
+	/// The format is compressed, or its samples can't be described
+	/// by any of the other variants.
+	Unknown,
+}
+
+/// Basic properties of an audio stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleSpec {
+	/// The sample rate, in Hz
+	pub rate :u32,
+	pub channels :u16,
+	pub format :SampleFormat,
+	/// Whether the samples are stored in big endian byte order
+	///
+	/// Always `false` for 8 bit samples, and if the format is unknown.
+	pub big_endian :bool,
+}
+
+impl<'a> From<&'a AudioDescription> for SampleSpec {
+	/// Extracts the sample spec from an audio description
+	///
+	/// Only Linear PCM descriptions have a sample format.
+	/// For compressed formats, the format of the samples is up to the
+	/// decoder, so `format` will be `SampleFormat::Unknown`.
+	/// The sample rate is rounded to the nearest integer.
+	/// Linear PCM without the little endian flag is big endian.
+	fn from(desc :&'a AudioDescription) -> Self {
+		let format = if desc.format_id != FormatType::LinearPcm {
+			SampleFormat::Unknown
+		} else if desc.format_flags & FLAG_IS_FLOAT != 0 {
+			match desc.bits_per_channel {
+				32 => SampleFormat::F32,
+				64 => SampleFormat::F64,
+				_ => SampleFormat::Unknown,
+			}
+		} else {
+			match desc.bits_per_channel {
+				8 => SampleFormat::I8,
+				16 => SampleFormat::I16,
+				24 => SampleFormat::I24,
+				32 => SampleFormat::I32,
+				_ => SampleFormat::Unknown,
+			}
+		};
+		let big_endian = match format {
+			SampleFormat::I8 | SampleFormat::Unknown => false,
+			_ => desc.format_flags & FLAG_IS_LITTLE_ENDIAN == 0,
+		};
+		SampleSpec {
+			rate : math::round(desc.sample_rate) as u32,
+			channels : desc.channels_per_frame as u16,
+			format : format,
+			big_endian : big_endian,
+		}
+	}
+}
+
+impl SampleSpec {
+	/// Returns the bit depth of a sample, or `None` if unknown
+	pub fn bits_per_sample(&self) -> Option<u32> {
+		use self::SampleFormat::*;
+		match self.format {
+			I8 => Some(8),
+			I16 => Some(16),
+			I24 => Some(24),
+			I32 | F32 => Some(32),
+			F64 => Some(64),
+			Unknown => None,
+		}
+	}
+	/// Creates an audio description for interleaved Linear PCM
+	///
+	/// The samples are described as packed, with one frame per packet,
+	/// and with the byte order given by `big_endian`.
+	/// Returns `None` if the format is `SampleFormat::Unknown`.
+	pub fn audio_description(&self) -> Option<AudioDescription> {
+		let bits = match self.bits_per_sample() {
+			Some(v) => v,
+			None => return None,
+		};
+		let float_flag = match self.format {
+			SampleFormat::F32 | SampleFormat::F64 => FLAG_IS_FLOAT,
+			_ => 0,
+		};
+		let endian_flag = if self.big_endian { 0 } else { FLAG_IS_LITTLE_ENDIAN };
+		Some(AudioDescription {
+			sample_rate : self.rate as f64,
+			format_id : FormatType::LinearPcm,
+			format_flags : float_flag | endian_flag,
+			bytes_per_packet : bits / 8 * self.channels as u32,
+			frames_per_packet : 1,
+			channels_per_frame : self.channels as u32,
+			bits_per_channel : bits,
+		})
+	}
+}
@@ -0,0 +1,61 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+/*!
+Variable length quantity encoding, as used by the packet table
+*/
+
+use io::Read;
+use io::{Error as IoError, ErrorKind};
+use alloc::vec::Vec;
+
+pub fn write_vlq(res :&mut Vec<u8>, v :u64) {
+	let mut shift = 63 / 7 * 7;
+	// Skip the leading zero groups, the encoding is minimal.
+	while shift > 0 && (v >> shift) == 0 {
+		shift -= 7;
+	}
+	while shift > 0 {
+		res.push((((v >> shift) & 127) as u8) | 128);
+		shift -= 7;
+	}
+	res.push((v & 127) as u8);
+}
+
+/// Returns the number of bytes the minimal encoding of `v` occupies
+pub fn vlq_width(v :u64) -> u8 {
+	let mut width = 1;
+	while width < 10 && (v >> (7 * width)) != 0 {
+		width += 1;
+	}
+	width as u8
+}
+
+/// Reads a VLQ encoded value
+///
+/// Returns an `InvalidData` error if the value doesn't fit into 64 bits,
+/// or if it isn't terminated within 10 bytes, the length of the
+/// minimal encoding of the largest 64 bit value.
+pub fn read_vlq<T :Read>(rdr :&mut T) -> Result<u64, IoError> {
+	let mut res :u64 = 0;
+	let mut buf = [0; 1];
+	for _ in 0 .. 10 {
+		try!(rdr.read_exact(&mut buf));
+		let byte = buf[0];
+		if res >> (64 - 7) != 0 {
+			break;
+		}
+		res <<= 7;
+		res |= (byte & 127) as u64;
+		if byte & 128 == 0 {
+			return Ok(res);
+		}
+	}
+	Err(IoError::new(ErrorKind::InvalidData,
+		"VLQ encoded value doesn't fit into 64 bits"))
+}
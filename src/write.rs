@@ -0,0 +1,232 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+/*!
+CAF encoding
+*/
+
+use io::{Read, Write, Seek, SeekFrom};
+use io::WriteBytesExt;
+use alloc::vec::Vec;
+use alloc::string::String;
+use byteorder::{BigEndian as Be, ByteOrder};
+use ::CafError;
+use ::ChunkType;
+use ::CAF_HEADER_MAGIC;
+use ::CafChunkReader;
+use chunks::{self, CafChunk, CafChunkHeader, AudioDescription, PacketTable};
+
+/// Writes a chunk header with the given type and content size
+fn write_chunk_header<W :Write>(wtr :&mut W, ch_type :ChunkType, ch_size :i64)
+		-> Result<(), CafError> {
+	try!(wtr.write_u32::<Be>(ch_type.into()));
+	try!(wtr.write_i64::<Be>(ch_size));
+	Ok(())
+}
+
+/// Writes a chunk, including its header
+fn write_chunk<W :Write>(wtr :&mut W, chunk :&CafChunk) -> Result<(), CafError> {
+	let content = try!(chunks::encode_chunk(chunk));
+	try!(write_chunk_header(wtr, chunk.get_type(), content.len() as i64));
+	try!(wtr.write_all(&content));
+	Ok(())
+}
+
+/**
+Low level writer for CAF files
+
+This is the counterpart of the `CafChunkReader`: it writes
+the file header, and then chunks, one after another.
+It doesn't check whether the written chunks form a valid CAF file,
+e.g. that the audio description is the first chunk.
+*/
+pub struct CafWriter<W> where W :Write {
+	wtr :W,
+}
+
+impl<T> CafWriter<T> where T :Write {
+	/// Creates a new writer and writes the file header
+	pub fn new(mut wtr :T) -> Result<Self, CafError> {
+		try!(wtr.write_all(&CAF_HEADER_MAGIC));
+		Ok(CafWriter {
+			wtr : wtr,
+		})
+	}
+	/// Returns the wrapped writer
+	pub fn into_inner(self) -> T {
+		self.wtr
+	}
+	/// Encodes a chunk and writes it, including its header
+	pub fn write_chunk(&mut self, chunk :&CafChunk) -> Result<(), CafError> {
+		write_chunk(&mut self.wtr, chunk)
+	}
+	/// Writes a chunk header
+	///
+	/// The body has to be written afterwards via `write_chunk_body`,
+	/// and needs to have the size given in the header.
+	pub fn write_chunk_header(&mut self, hdr :&CafChunkHeader) -> Result<(), CafError> {
+		write_chunk_header(&mut self.wtr, hdr.ch_type, hdr.ch_size)
+	}
+	/// Writes (a part of) a chunk body
+	pub fn write_chunk_body(&mut self, data :&[u8]) -> Result<(), CafError> {
+		try!(self.wtr.write_all(data));
+		Ok(())
+	}
+}
+
+/**
+Writer for non-seekable targets
+
+Writing a CAF file with a packet table to a target that doesn't
+support seeking is tricky: the packet table has to precede the
+audio data chunk, but the packet lengths are only known once
+all packets have been written.
+
+This writer solves the problem by buffering the packet lengths
+as well as the packets themselves in memory until `finish` is
+called, which then writes the whole file in one go.
+The tradeoff is memory usage: all of the audio data is held in memory
+until the writer is finished. If your target supports seeking, or if
+you know the packet lengths upfront, you don't need to pay this cost.
+*/
+pub struct TwoPassWriter<W> where W :Write {
+	wtr :W,
+	audio_desc :AudioDescription,
+	chunks :Vec<CafChunk>,
+	packet_lengths :Vec<u64>,
+	audio_data :Vec<u8>,
+	edit_count :u32,
+	num_valid_frames :Option<i64>,
+	num_priming_frames :i32,
+	num_remainder_frames :i32,
+}
+
+impl<T> TwoPassWriter<T> where T :Write {
+	/// Creates a new writer for the given audio description
+	///
+	/// Nothing is written to `wtr` until `finish` is called.
+	pub fn new(wtr :T, audio_desc :AudioDescription) -> Self {
+		TwoPassWriter {
+			wtr : wtr,
+			audio_desc : audio_desc,
+			chunks : Vec::new(),
+			packet_lengths : Vec::new(),
+			audio_data : Vec::new(),
+			edit_count : 0,
+			num_valid_frames : None,
+			num_priming_frames : 0,
+			num_remainder_frames : 0,
+		}
+	}
+	/// Adds a chunk to be written before the audio data chunk
+	///
+	/// The `Desc`, `PacketTable` and `AudioDataInMemory` chunks are
+	/// generated by the writer itself and must not be added.
+	pub fn add_chunk(&mut self, chunk :CafChunk) {
+		self.chunks.push(chunk);
+	}
+	/// Sets the edit count stored in the audio data chunk
+	pub fn set_edit_count(&mut self, edit_count :u32) {
+		self.edit_count = edit_count;
+	}
+	/// Sets the frame counts stored in the packet table
+	///
+	/// If this function isn't called, there are no priming or remainder
+	/// frames, and the number of valid frames is derived from the
+	/// number of packets and the number of frames per packet.
+	pub fn set_frame_counts(&mut self, num_valid_frames :i64,
+			num_priming_frames :i32, num_remainder_frames :i32) {
+		self.num_valid_frames = Some(num_valid_frames);
+		self.num_priming_frames = num_priming_frames;
+		self.num_remainder_frames = num_remainder_frames;
+	}
+	/// Adds a packet to the audio data
+	pub fn write_packet(&mut self, packet :&[u8]) {
+		self.packet_lengths.push(packet.len() as u64);
+		self.audio_data.extend_from_slice(packet);
+	}
+	/// Writes the file and returns the wrapped writer
+	///
+	/// The chunks are written in the following order: the audio
+	/// description, all chunks added via `add_chunk`, the packet table
+	/// (only if the format requires one), and finally the audio data.
+	pub fn finish(self) -> Result<T, CafError> {
+		let mut wtr = try!(CafWriter::new(self.wtr));
+		try!(wtr.write_chunk(&CafChunk::Desc(self.audio_desc.clone())));
+		for chunk in self.chunks.iter() {
+			try!(wtr.write_chunk(chunk));
+		}
+		let p_table_required = self.audio_desc.bytes_per_packet == 0 ||
+			self.audio_desc.frames_per_packet == 0;
+		if p_table_required {
+			let num_valid_frames = match self.num_valid_frames {
+				Some(v) => v,
+				None => self.packet_lengths.len() as i64 *
+					self.audio_desc.frames_per_packet as i64,
+			};
+			let packet_table = PacketTable {
+				num_valid_frames : num_valid_frames,
+				num_priming_frames : self.num_priming_frames,
+				num_remainder_frames : self.num_remainder_frames,
+				lengths : self.packet_lengths,
+			};
+			try!(wtr.write_chunk(&CafChunk::PacketTable(packet_table)));
+		}
+		try!(wtr.write_chunk_header(&CafChunkHeader {
+			ch_type : ChunkType::AudioData,
+			ch_size : 4 + self.audio_data.len() as i64,
+		}));
+		let mut edit_count = [0; 4];
+		Be::write_u32(&mut edit_count, self.edit_count);
+		try!(wtr.write_chunk_body(&edit_count));
+		try!(wtr.write_chunk_body(&self.audio_data));
+		Ok(wtr.into_inner())
+	}
+}
+
+/**
+Replaces the content of the info chunk in place
+
+`file` needs to be positioned at the start of the CAF file.
+The first info chunk of the file gets located and its
+content gets overwritten with the given entries.
+
+Only replacing with content of the same size is supported,
+otherwise all the chunks after the info chunk would need to be moved.
+In that case, `CafError::ChunkSizeChanged` is returned, and
+the file needs to be rewritten instead.
+If the file has no info chunk, `CafError::MissingChunk` is returned.
+*/
+pub fn update_info_in_place<T>(file :&mut T, entries :Vec<(String, String)>)
+		-> Result<(), CafError> where T :Read + Write + Seek {
+	let content = try!(chunks::encode_chunk(&CafChunk::Info(entries)));
+	{
+		let mut rdr = try!(CafChunkReader::new(&mut *file));
+		let pos = try!(rdr.rdr.seek(SeekFrom::Current(0)));
+		let end = try!(rdr.rdr.seek(SeekFrom::End(0)));
+		try!(rdr.rdr.seek(SeekFrom::Start(pos)));
+		loop {
+			let hdr = match try!(rdr.read_chunk_header_before(end)) {
+				Some(hdr) => hdr,
+				None => return Err(CafError::MissingChunk(ChunkType::Info)),
+			};
+			if hdr.ch_type == ChunkType::Info {
+				if hdr.ch_size != content.len() as i64 {
+					return Err(CafError::ChunkSizeChanged);
+				}
+				break;
+			}
+			if hdr.ch_size == -1 {
+				return Err(CafError::MissingChunk(ChunkType::Info));
+			}
+			try!(rdr.to_next_chunk(&hdr));
+		}
+	}
+	try!(file.write_all(&content));
+	Ok(())
+}
@@ -0,0 +1,406 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+extern crate caf;
+
+mod common;
+
+use caf::io::{self, Cursor, Read, Seek, SeekFrom, ErrorKind};
+use caf::{CafChunkReader, ChunkType, CafError, WalkAction, FeatureSummary, read_info};
+use caf::chunks::{CafChunk, CafChunkHeader};
+use caf::write::update_info_in_place;
+use common::*;
+
+#[test]
+fn chunk_headers_trailing_garbage() {
+	let mut f = pcm_file(5);
+	f.extend_from_slice(&[b'A'; 50]);
+	let rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	let types = rdr.chunk_headers().map(|hdr| hdr.unwrap().ch_type)
+		.collect::<Vec<_>>();
+	assert_eq!(types, vec![ChunkType::AudioDescription,
+		ChunkType::Info, ChunkType::AudioData]);
+}
+
+#[test]
+fn read_chunk_raw_truncated() {
+	// A huge size must not be trusted for the allocation
+	let mut f = file(&[chunk_sized(b"free", i64::max_value(), &[0; 10])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f.clone())).unwrap();
+	let hdr = rdr.read_chunk_header().unwrap();
+	assert!(rdr.read_chunk_raw(&hdr).is_err());
+	let rdr = CafChunkReader::new(Cursor::new(f.clone())).unwrap();
+	assert!(rdr.into_chunk_iter().any(|c| c.is_err()));
+
+	f.extend_from_slice(&[b'A'; 50]);
+	let rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	assert!(rdr.into_chunk_iter().any(|c| c.is_err()));
+
+	let f = file(&[chunk(b"free", &[1, 2, 3])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	let hdr = rdr.read_chunk_header().unwrap();
+	assert_eq!(rdr.read_chunk_raw(&hdr).unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn read_chunks_to_mem_nothing_required() {
+	let f = file(&[]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	let (chunks, headers) = rdr.read_chunks_to_mem(vec![], &[]).unwrap();
+	assert!(chunks.is_empty());
+	assert!(headers.is_empty());
+
+	let mut rdr = CafChunkReader::new(Cursor::new(pcm_file(1))).unwrap();
+	let (chunks, headers) = rdr.read_chunks_to_mem(vec![],
+		&[ChunkType::AudioDescription]).unwrap();
+	assert_eq!(chunks.len(), 1);
+	assert_eq!(headers.len(), 1);
+}
+
+#[test]
+fn negative_chunk_size() {
+	let f = file(&[chunk_sized(b"free", -5, &[])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f.clone())).unwrap();
+	match rdr.read_chunk_header() {
+		Err(CafError::InvalidChunkSize(-5)) => (),
+		v => panic!("unexpected result {:?}", v),
+	}
+	let rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	assert_eq!(rdr.chunk_headers().count(), 0);
+
+	// Treated as trailing garbage after the last chunk
+	let mut f = pcm_file(2);
+	f.extend_from_slice(&chunk_sized(b"free", -5, &[]));
+	let rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	assert_eq!(rdr.chunk_headers().count(), 3);
+}
+
+#[test]
+fn data_is_last() {
+	let mut rdr = CafChunkReader::new(Cursor::new(pcm_file(4))).unwrap();
+	assert!(rdr.data_is_last().unwrap());
+	// The reader is rewound to where it was
+	assert_eq!(rdr.into_inner().seek(SeekFrom::Current(0)).unwrap(), 8);
+
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16),
+		data(0, &[0; 8]), info(&[("a", "b")])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	assert!(!rdr.data_is_last().unwrap());
+
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16),
+		data_unsized(0, &[0; 8])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	assert!(rdr.data_is_last().unwrap());
+}
+
+#[test]
+fn walk() {
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16), data(0, &[7; 100000]),
+		info(&[("a", "b")]), chunk(b"free", &[0; 3])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	let mut types = Vec::new();
+	let mut info_chunk = None;
+	let mut free_len = 0;
+	rdr.walk(|hdr, body| {
+		types.push(hdr.ch_type);
+		match hdr.ch_type {
+			ChunkType::Info => info_chunk = Some(body.read().unwrap()),
+			ChunkType::Free => {
+				let mut v = Vec::new();
+				body.reader().read_to_end(&mut v).unwrap();
+				free_len = v.len();
+			},
+			_ => (),
+		}
+		WalkAction::Continue
+	}).unwrap();
+	assert_eq!(types, vec![ChunkType::AudioDescription, ChunkType::AudioData,
+		ChunkType::Info, ChunkType::Free]);
+	assert_eq!(info_chunk, Some(CafChunk::Info(vec![("a".to_string(), "b".to_string())])));
+	assert_eq!(free_len, 3);
+
+	// Stopping leaves the reader after the current chunk
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16), data(0, &[7; 100]),
+		info(&[("a", "b")])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	let mut n = 0;
+	rdr.walk(|_, _| {
+		n += 1;
+		if n == 2 { WalkAction::Stop } else { WalkAction::Continue }
+	}).unwrap();
+	assert_eq!(rdr.read_chunk_header().unwrap().ch_type, ChunkType::Info);
+}
+
+#[test]
+fn feature_summary() {
+	let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0), chunk(b"kuki", &[1, 2]),
+		info(&[]), pakt(0, 0, 0, &[1]), data_unsized(0, &[1])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	assert_eq!(rdr.feature_summary().unwrap(), FeatureSummary {
+		has_markers : false,
+		has_regions : false,
+		has_channel_layout : false,
+		has_magic_cookie : true,
+		has_info : true,
+		is_vbr : true,
+		is_streaming_layout : true,
+	});
+	assert_eq!(rdr.into_inner().seek(SeekFrom::Current(0)).unwrap(), 8);
+
+	let mut rdr = CafChunkReader::new(Cursor::new(pcm_file(2))).unwrap();
+	let summary = rdr.feature_summary().unwrap();
+	assert!(!summary.is_vbr);
+	assert!(!summary.is_streaming_layout);
+	assert!(summary.has_info);
+}
+
+#[test]
+fn read_header() {
+	let mut c = Cursor::new(pcm_file(1));
+	assert_eq!(CafChunkReader::read_header(&mut c).unwrap(), (1, 0));
+	// Unknown versions are returned, but rejected by the constructor
+	let mut f = pcm_file(1);
+	f[5] = 2;
+	assert_eq!(CafChunkReader::read_header(&mut Cursor::new(f.clone())).unwrap(), (2, 0));
+	match CafChunkReader::new(Cursor::new(f)) {
+		Err(CafError::NotCaf) => (),
+		_ => panic!("expected a NotCaf error"),
+	}
+	let mut f = pcm_file(1);
+	f[0] = b'x';
+	match CafChunkReader::read_header(&mut Cursor::new(f)) {
+		Err(CafError::NotCaf) => (),
+		_ => panic!("expected a NotCaf error"),
+	}
+}
+
+#[test]
+fn scans_ignore_trailing_garbage() {
+	let mut f = pcm_file(3);
+	f.extend((0 .. 50u8).map(|i| i.wrapping_mul(97)));
+	let mut rdr = CafChunkReader::new(Cursor::new(f.clone())).unwrap();
+	assert!(rdr.data_is_last().unwrap());
+	let mut n = 0;
+	rdr.walk(|_, _| {
+		n += 1;
+		WalkAction::Continue
+	}).unwrap();
+	assert_eq!(n, 3);
+	let mut rdr = CafChunkReader::new(Cursor::new(f.clone())).unwrap();
+	assert!(rdr.feature_summary().unwrap().has_info);
+	update_info_in_place(&mut Cursor::new(f),
+		vec![("title".to_string(), "z".to_string())]).unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn chunk_type_counts() {
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16), info(&[]),
+		data(0, &[0; 8]), info(&[("a", "b")])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	let counts = rdr.chunk_type_counts().unwrap();
+	assert_eq!(counts[&ChunkType::Info], 2);
+	assert_eq!(counts[&ChunkType::AudioData], 1);
+	assert_eq!(counts.len(), 3);
+}
+
+#[test]
+fn read_unsized_audio_chunk() {
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16),
+		data_unsized(3, &[1, 2, 3, 4])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	rdr.read_chunk().unwrap();
+	let audio_data = rdr.read_chunk().unwrap().audio_data().cloned().unwrap();
+	assert_eq!(audio_data.into_parts(), (3, vec![1, 2, 3, 4]));
+}
+
+/// A reader that can't seek, and returns at most 3 bytes per read
+struct Trickle(Cursor<Vec<u8>>);
+
+impl Read for Trickle {
+	fn read(&mut self, buf :&mut [u8]) -> io::Result<usize> {
+		let len = ::std::cmp::min(3, buf.len());
+		self.0.read(&mut buf[.. len])
+	}
+}
+
+#[test]
+fn chunk_iter() {
+	let f = pcm_file(4);
+	let chunks = CafChunkReader::new(Trickle(Cursor::new(f.clone()))).unwrap()
+		.into_chunk_iter().collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(chunks.len(), 3);
+	assert_eq!(chunks[1], CafChunk::Info(vec![("title".to_string(), "x".to_string())]));
+
+	// A partial chunk header at the end is an error
+	let mut f = f;
+	f.extend_from_slice(&[1, 2, 3]);
+	let chunks = CafChunkReader::new(Trickle(Cursor::new(f))).unwrap()
+		.into_chunk_iter().collect::<Vec<_>>();
+	assert_eq!(chunks.len(), 4);
+	assert!(chunks[3].is_err());
+}
+
+#[test]
+fn try_to_next_chunk() {
+	let mut rdr = CafChunkReader::new(Cursor::new(pcm_file(2))).unwrap();
+	let unsized_hdr = CafChunkHeader {
+		ch_type : ChunkType::AudioData,
+		ch_size : -1,
+	};
+	match rdr.try_to_next_chunk(&unsized_hdr) {
+		Err(CafError::UnspecifiedChunkSize) => (),
+		r => panic!("expected an unspecified size error, got {:?}", r),
+	}
+	match rdr.try_to_previous_chunk(&unsized_hdr) {
+		Err(CafError::UnspecifiedChunkSize) => (),
+		r => panic!("expected an unspecified size error, got {:?}", r),
+	}
+	let hdr = rdr.read_chunk_header().unwrap();
+	rdr.try_to_next_chunk(&hdr).unwrap();
+	assert_eq!(rdr.read_chunk_header().unwrap().ch_type, ChunkType::Info);
+}
+
+#[test]
+fn position() {
+	let mut rdr = CafChunkReader::new(Cursor::new(pcm_file(2))).unwrap();
+	assert_eq!(rdr.position().unwrap(), 8);
+	let hdr = rdr.read_chunk_header().unwrap();
+	assert_eq!(rdr.position().unwrap(), 20);
+	rdr.to_next_chunk(&hdr).unwrap();
+	assert_eq!(rdr.position().unwrap(), 20 + hdr.ch_size as u64);
+	let hdr_2 = rdr.read_chunk_header().unwrap();
+	rdr.to_next_chunk(&hdr_2).unwrap();
+	assert_eq!(rdr.position().unwrap(), 32 + (hdr.ch_size + hdr_2.ch_size) as u64);
+}
+
+#[test]
+fn peek_chunk_header() {
+	let mut rdr = CafChunkReader::new(Cursor::new(pcm_file(2))).unwrap();
+	let peeked = rdr.peek_chunk_header().unwrap();
+	assert_eq!(rdr.position().unwrap(), 8);
+	let hdr = rdr.read_chunk_header().unwrap();
+	assert_eq!((peeked.ch_type, peeked.ch_size), (hdr.ch_type, hdr.ch_size));
+	assert_eq!(hdr.ch_type, ChunkType::AudioDescription);
+	rdr.to_next_chunk(&hdr).unwrap();
+	assert_eq!(rdr.peek_chunk_header().unwrap().ch_type, ChunkType::Info);
+	assert_eq!(rdr.read_chunk_header().unwrap().ch_type, ChunkType::Info);
+}
+
+#[test]
+fn read_chunk_header_opt() {
+	let mut rdr = CafChunkReader::new(Cursor::new(pcm_file(2))).unwrap();
+	let mut n = 0;
+	while let Some(hdr) = rdr.read_chunk_header_opt().unwrap() {
+		rdr.read_chunk_body(&hdr).unwrap();
+		n += 1;
+	}
+	assert_eq!(n, 3);
+
+	// EOF inside of a chunk header is still an error
+	let mut f = pcm_file(2);
+	f.extend_from_slice(b"fr");
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	for _ in 0 .. 3 {
+		let hdr = rdr.read_chunk_header_opt().unwrap().unwrap();
+		rdr.to_next_chunk(&hdr).unwrap();
+	}
+	match rdr.read_chunk_header_opt() {
+		Err(CafError::Io(ref e)) if e.kind() == ErrorKind::UnexpectedEof => (),
+		r => panic!("expected an EOF error, got {:?}", r),
+	}
+}
+
+#[test]
+fn chunk_body_reader() {
+	let mut rdr = CafChunkReader::new(Cursor::new(pcm_file(2))).unwrap();
+	let hdr = rdr.read_chunk_header().unwrap();
+	{
+		let mut body = rdr.chunk_body_reader(&hdr);
+		let mut buf = [0; 5];
+		let mut len = 0;
+		loop {
+			let n = body.read(&mut buf).unwrap();
+			if n == 0 {
+				break;
+			}
+			len += n;
+		}
+		assert_eq!(len, 32);
+	}
+	let hdr = rdr.read_chunk_header().unwrap();
+	assert_eq!(hdr.ch_type, ChunkType::Info);
+	{
+		// Dropping the reader skips the rest of the body
+		let mut body = rdr.chunk_body_reader(&hdr);
+		let mut buf = [0; 3];
+		body.read_exact(&mut buf).unwrap();
+	}
+	assert_eq!(rdr.read_chunk_header().unwrap().ch_type, ChunkType::AudioData);
+}
+
+#[test]
+fn read_info_entries() {
+	assert_eq!(read_info(Cursor::new(pcm_file(4))).unwrap(),
+		vec![("title".to_string(), "x".to_string())]);
+	// No info chunk before the unsized audio data chunk
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16), data_unsized(0, &[1, 2, 3, 4])]);
+	assert_eq!(read_info(Cursor::new(f)).unwrap(), vec![]);
+	// Info chunk after the audio data
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16), data(0, &[0; 8]),
+		info(&[("a", "b")])]);
+	assert_eq!(read_info(Cursor::new(f)).unwrap(), vec![("a".to_string(), "b".to_string())]);
+}
+
+#[test]
+fn seek_to_chunk() {
+	let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0), info(&[("a", "b")]),
+		chunk(b"free", &[0; 7]), pakt(1024, 0, 0, &[3]), data(0, &[1, 2, 3])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	let hdr = rdr.seek_to_chunk(ChunkType::PacketTable).unwrap().unwrap();
+	assert_eq!(hdr.ch_type, ChunkType::PacketTable);
+	match rdr.read_chunk_body(&hdr).unwrap() {
+		CafChunk::PacketTable(table) => assert_eq!(table.lengths, vec![3]),
+		_ => panic!("expected a packet table"),
+	}
+	let hdr = rdr.seek_to_chunk(ChunkType::AudioData).unwrap().unwrap();
+	assert_eq!(hdr.ch_size, 7);
+	rdr.to_next_chunk(&hdr).unwrap();
+	// Only searches forward
+	assert!(rdr.seek_to_chunk(ChunkType::Info).unwrap().is_none());
+
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16), data_unsized(0, &[1, 2, 3, 4])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f.clone())).unwrap();
+	assert!(rdr.seek_to_chunk(ChunkType::Info).unwrap().is_none());
+	let mut rdr = CafChunkReader::new(Cursor::new(f)).unwrap();
+	assert_eq!(rdr.seek_to_chunk(ChunkType::AudioData).unwrap().unwrap().ch_size, -1);
+}
+
+#[test]
+fn skip_chunk() {
+	let f = pcm_file(2);
+	let mut rdr = CafChunkReader::new(Trickle(Cursor::new(f.clone()))).unwrap();
+	let hdr = rdr.read_chunk_header().unwrap();
+	rdr.skip_chunk(&hdr).unwrap();
+	let hdr = rdr.read_chunk_header().unwrap();
+	assert_eq!(hdr.ch_type, ChunkType::Info);
+	rdr.skip_chunk(&hdr).unwrap();
+	let mut hdr = rdr.read_chunk_header().unwrap();
+	assert_eq!(hdr.ch_type, ChunkType::AudioData);
+	// Past the end of the file
+	hdr.ch_size += 1;
+	assert!(rdr.skip_chunk(&hdr).is_err());
+
+	let mut rdr = CafChunkReader::new(Trickle(Cursor::new(f))).unwrap();
+	let mut hdr = rdr.read_chunk_header().unwrap();
+	hdr.ch_size = -1;
+	match rdr.skip_chunk(&hdr) {
+		Err(CafError::UnspecifiedChunkSize) => (),
+		r => panic!("expected an unspecified size error, got {:?}", r),
+	}
+}
@@ -0,0 +1,509 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+extern crate caf;
+
+use std::f64;
+use caf::io::ErrorKind;
+use caf::{ChunkType, CafError, FormatType, SampleSpec, SampleFormat};
+use caf::chunks::{CafChunk, CafMarker, Markers, SmpteTime, PacketTable, AudioData};
+use caf::chunks::{AudioDescription, ChannelLayout};
+use caf::chunks::{decode_chunk, encode_chunk, can_decode_chunk_type};
+
+fn marker(marker_id :u32, frame_position :f64) -> CafMarker {
+	CafMarker {
+		marker_type : 0,
+		frame_position : frame_position,
+		marker_id : marker_id,
+		smpte_time : SmpteTime {
+			hours : 0,
+			minutes : 0,
+			seconds : 0,
+			frames : 0,
+			sub_frame_sample_offset : 0,
+		},
+		channel : 0,
+	}
+}
+
+#[test]
+fn markers_by_time_nan_last() {
+	let markers = (0 .. 200).map(|i| {
+		// Scramble the ids so the file order differs from the id order
+		let id = (i * 7919) % 200;
+		let pos = if i % 3 == 0 { f64::NAN } else { ((i * 31) % 50) as f64 };
+		marker(id, pos)
+	}).collect();
+	let chunk = CafChunk::Markers(Markers {
+		smpte_time_type : 0,
+		markers : markers,
+	});
+	let sorted = chunk.markers_by_time();
+	assert_eq!(sorted.len(), 200);
+	let nan_start = sorted.iter().position(|m| m.frame_position.is_nan()).unwrap();
+	assert_eq!(nan_start, 200 - 67);
+	assert!(sorted[nan_start..].iter().all(|m| m.frame_position.is_nan()));
+	for w in sorted[..nan_start].windows(2) {
+		assert!(w[0].frame_position < w[1].frame_position
+			|| (w[0].frame_position == w[1].frame_position
+				&& w[0].marker_id < w[1].marker_id));
+	}
+	for w in sorted[nan_start..].windows(2) {
+		assert!(w[0].marker_id < w[1].marker_id);
+	}
+}
+
+fn pakt_body(lengths :&[u8]) -> Vec<u8> {
+	// One packet, no frame counts
+	let mut body = vec![0; 24];
+	body[7] = 1;
+	body.extend_from_slice(lengths);
+	body
+}
+
+#[test]
+fn packet_table_overlong_vlq() {
+	// Eleven continuation bytes exceed the 64 bits of a length
+	let mut lengths = vec![0xff; 11];
+	lengths.push(0);
+	match decode_chunk(ChunkType::PacketTable, pakt_body(&lengths)) {
+		Err(CafError::Io(ref e)) if e.kind() == ErrorKind::InvalidData => (),
+		v => panic!("unexpected result {:?}", v),
+	}
+	// Padding with zero bits doesn't make a length valid either
+	let mut lengths = vec![0x80; 20];
+	lengths.push(0);
+	assert!(decode_chunk(ChunkType::PacketTable, pakt_body(&lengths)).is_err());
+
+	// The largest lengths still round trip
+	let table = PacketTable {
+		num_valid_frames : 0,
+		num_priming_frames : 0,
+		num_remainder_frames : 0,
+		lengths : vec![u64::max_value(), 0, 1 << 63],
+	};
+	let body = encode_chunk(&CafChunk::PacketTable(table.clone())).unwrap();
+	match decode_chunk(ChunkType::PacketTable, body).unwrap() {
+		CafChunk::PacketTable(t) => assert_eq!(t.lengths, table.lengths),
+		_ => panic!("wrong chunk type"),
+	}
+}
+
+#[test]
+fn audio_data_in_memory() {
+	let chunk = decode_chunk(ChunkType::AudioData, vec![0, 0, 0, 3, 7, 8, 9]).unwrap();
+	match chunk {
+		CafChunk::AudioDataInMemory(AudioData { edit_count, ref data }) => {
+			assert_eq!(edit_count, 3);
+			assert_eq!(data, &[7, 8, 9]);
+		},
+		_ => panic!("wrong chunk type"),
+	}
+	assert_eq!(chunk.audio_data(), Some(&AudioData::new(3, vec![7, 8, 9])));
+	#[allow(deprecated)]
+	let chunk_compat = CafChunk::audio_data_in_memory(3, vec![7, 8, 9]);
+	assert_eq!(chunk_compat, chunk);
+	assert_eq!(chunk.audio_data().unwrap().clone().into_parts(), (3, vec![7, 8, 9]));
+	assert_eq!(CafChunk::Info(Vec::new()).audio_data(), None);
+}
+
+fn aac_desc() -> AudioDescription {
+	AudioDescription {
+		sample_rate : 44100.0,
+		format_id : FormatType::Mpeg4Aac,
+		format_flags : 0,
+		bytes_per_packet : 0,
+		frames_per_packet : 1024,
+		channels_per_frame : 2,
+		bits_per_channel : 0,
+	}
+}
+
+#[test]
+fn aac_frames_per_packet() {
+	let mut desc = aac_desc();
+	desc.format_flags = 2;
+	desc.frames_per_packet = 960;
+	assert_eq!(desc.aac_frames_per_packet(), Some(960));
+	desc.frames_per_packet = 0;
+	assert_eq!(desc.aac_frames_per_packet(), Some(1024));
+	// HE-AAC
+	desc.format_flags = 5;
+	assert_eq!(desc.aac_frames_per_packet(), Some(2048));
+	desc.format_id = FormatType::LinearPcm;
+	assert_eq!(desc.aac_frames_per_packet(), None);
+}
+
+#[test]
+fn frame_count_discrepancy() {
+	let mut table = PacketTable {
+		num_valid_frames : 10 * 1024 - 2112 - 100,
+		num_priming_frames : 2112,
+		num_remainder_frames : 100,
+		lengths : vec![1; 10],
+	};
+	assert_eq!(table.frame_count_discrepancy(1024), Some(0));
+	table.num_valid_frames += 5;
+	assert_eq!(table.frame_count_discrepancy(1024), Some(-5));
+	assert_eq!(table.frame_count_discrepancy(0), None);
+}
+
+#[test]
+fn effective_bits_per_channel() {
+	let mut desc = AudioDescription {
+		sample_rate : 8000.0,
+		format_id : FormatType::Alaw,
+		format_flags : 0,
+		bytes_per_packet : 1,
+		frames_per_packet : 1,
+		channels_per_frame : 1,
+		bits_per_channel : 0,
+	};
+	assert_eq!(desc.effective_bits_per_channel(), 16);
+	desc.format_id = FormatType::LinearPcm;
+	desc.bytes_per_packet = 3;
+	assert_eq!(desc.effective_bits_per_channel(), 24);
+	desc.bits_per_channel = 8;
+	assert_eq!(desc.effective_bits_per_channel(), 8);
+}
+
+#[test]
+fn overview_samples_for_channel() {
+	// Edit count 1, 256 frames per overview sample
+	let mut body = vec![0, 0, 0, 1, 0, 0, 1, 0];
+	for i in 1 .. 6i16 {
+		body.extend_from_slice(&(-i as u16).to_be_bytes());
+		body.extend_from_slice(&(i as u16).to_be_bytes());
+	}
+	let ch = decode_chunk(ChunkType::Overview, body.clone()).unwrap();
+	assert_eq!(encode_chunk(&ch).unwrap(), body);
+	let overview = match ch {
+		CafChunk::Overview(o) => o,
+		_ => panic!("expected an overview chunk"),
+	};
+	assert_eq!(overview.frames_per_overview_sample, 256);
+	assert_eq!(overview.samples_for_channel(0, 2).unwrap().collect::<Vec<_>>(),
+		vec![(-1, 1), (-3, 3), (-5, 5)]);
+	assert_eq!(overview.samples_for_channel(1, 2).unwrap().collect::<Vec<_>>(),
+		vec![(-2, 2), (-4, 4)]);
+	assert!(overview.samples_for_channel(2, 2).is_none());
+}
+
+#[test]
+fn raw_sample_rate_bits() {
+	for &rate in [44100.0, 22050.5, 11024.999999, 1e-300].iter() {
+		let mut desc = aac_desc();
+		desc.sample_rate = rate;
+		assert_eq!(desc.raw_sample_rate_bits(), rate.to_bits());
+		let encoded = encode_chunk(&CafChunk::Desc(desc)).unwrap();
+		assert_eq!(&encoded[.. 8], &rate.to_bits().to_be_bytes());
+	}
+}
+
+#[test]
+fn truncated_chunk_bodies() {
+	let min_sizes = [(ChunkType::AudioDescription, 32), (ChunkType::ChannelLayout, 12),
+		(ChunkType::AudioData, 4)];
+	for &(ch_type, min_size) in min_sizes.iter() {
+		for len in 0 .. min_size {
+			match decode_chunk(ch_type, vec![0; len]) {
+				Err(CafError::Io(_)) => (),
+				r => panic!("{:?} with {} bytes: {:?}", ch_type, len, r),
+			}
+		}
+	}
+}
+
+#[test]
+fn vlq_widths() {
+	let lengths = vec![0, 1, 127, 128, 16383, 16384, 2097151, 2097152, u64::max_value()];
+	let table = PacketTable {
+		num_valid_frames : 0,
+		num_priming_frames : 0,
+		num_remainder_frames : 0,
+		lengths : lengths,
+	};
+	let widths = table.vlq_widths();
+	assert_eq!(widths, vec![1, 1, 1, 2, 2, 3, 3, 4, 10]);
+	let encoded = encode_chunk(&CafChunk::PacketTable(table)).unwrap();
+	assert_eq!(encoded.len(), 24 + widths.iter().map(|&w| w as usize).sum::<usize>());
+}
+
+#[test]
+fn audio_description_display() {
+	let desc = SampleSpec {
+		rate : 48000,
+		channels : 2,
+		format : SampleFormat::I16,
+		big_endian : false,
+	}.audio_description().unwrap();
+	assert_eq!(desc.to_string(), "48000 Hz, 2 ch, lpcm (16-bit), 4 bytes/packet");
+	assert_eq!(aac_desc().to_string(),
+		"44100 Hz, 2 ch, aac, variable bytes/packet, 1024 frames/packet");
+}
+
+#[test]
+fn strings_chunk() {
+	let mut body = vec![0, 0, 0, 2];
+	body.extend_from_slice(&[0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 0]);
+	body.extend_from_slice(&[0, 0, 0, 9, 0, 0, 0, 0, 0, 0, 0, 4]);
+	body.extend_from_slice(b"abc\0hello\0");
+	let ch = decode_chunk(ChunkType::Strings, body.clone()).unwrap();
+	assert_eq!(ch, CafChunk::Strings(vec![(5, "abc".to_string()), (9, "hello".to_string())]));
+	assert_eq!(encode_chunk(&ch).unwrap(), body);
+	assert_eq!(ChunkType::from(0x73747267), ChunkType::Strings);
+	// Offset past the end of the string data
+	body[15] = 50;
+	assert!(decode_chunk(ChunkType::Strings, body).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn info_map() {
+	let ch = CafChunk::Info(vec![("artist".to_string(), "a".to_string()),
+		("title".to_string(), "t".to_string()), ("artist".to_string(), "b".to_string())]);
+	let map = ch.info_map().unwrap();
+	assert_eq!(map.len(), 2);
+	// The last entry wins
+	assert_eq!(map["artist"], "b");
+	assert!(CafChunk::MagicCookie(Vec::new()).info_map().is_none());
+}
+
+fn marker_bytes(frame_position :f64) -> Vec<u8> {
+	let mut res = vec![0, 0, 0, 1];
+	res.extend_from_slice(&frame_position.to_bits().to_be_bytes());
+	res.extend_from_slice(&[0, 0, 0, 3, 1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0]);
+	res
+}
+
+#[test]
+fn regions_chunk() {
+	// SMPTE time type, two regions
+	let mut body = vec![0, 0, 0, 0, 0, 0, 0, 2];
+	// Region 7: loop, play forward, two markers
+	body.extend_from_slice(&[0, 0, 0, 7, 0, 0, 0, 3, 0, 0, 0, 2]);
+	body.extend_from_slice(&marker_bytes(100.0));
+	body.extend_from_slice(&marker_bytes(200.0));
+	// Region 8: play backward, no markers
+	body.extend_from_slice(&[0, 0, 0, 8, 0, 0, 0, 4, 0, 0, 0, 0]);
+	let ch = decode_chunk(ChunkType::Region, body.clone()).unwrap();
+	match ch {
+		CafChunk::Regions(ref regions) => {
+			let regions = &regions.regions;
+			assert_eq!(regions.len(), 2);
+			assert!(regions[0].is_loop());
+			assert!(regions[0].flags.play_forward());
+			assert!(!regions[0].flags.play_backward());
+			assert_eq!(regions[0].markers[1].frame_position, 200.0);
+			assert!(!regions[1].is_loop());
+			assert!(regions[1].flags.play_backward());
+		},
+		_ => panic!("expected a regions chunk"),
+	}
+	assert_eq!(encode_chunk(&ch).unwrap(), body);
+}
+
+#[test]
+fn audio_data_edit_count() {
+	let body = (0 .. 100).collect::<Vec<u8>>();
+	let ch = decode_chunk(ChunkType::AudioData, body.clone()).unwrap();
+	let audio_data = ch.audio_data().unwrap();
+	assert_eq!(audio_data.edit_count, 0x00010203);
+	assert_eq!(audio_data.data, &body[4 ..]);
+}
+
+#[test]
+fn get_type() {
+	let empty_table = PacketTable {
+		num_valid_frames : 0,
+		num_priming_frames : 0,
+		num_remainder_frames : 0,
+		lengths : Vec::new(),
+	};
+	let empty_layout = ChannelLayout {
+		channel_layout_tag : 0,
+		channel_bitmap : 0,
+		channel_descriptions : Vec::new(),
+	};
+	assert_eq!(CafChunk::Desc(aac_desc()).get_type(), ChunkType::AudioDescription);
+	assert_eq!(CafChunk::AudioDataInMemory(AudioData::new(0, Vec::new())).get_type(),
+		ChunkType::AudioData);
+	assert_eq!(CafChunk::PacketTable(empty_table).get_type(), ChunkType::PacketTable);
+	assert_eq!(CafChunk::ChanLayout(empty_layout).get_type(), ChunkType::ChannelLayout);
+	assert_eq!(CafChunk::MagicCookie(Vec::new()).get_type(), ChunkType::MagicCookie);
+	assert_eq!(CafChunk::Info(Vec::new()).get_type(), ChunkType::Info);
+}
+
+#[test]
+fn validate_audio_description() {
+	let desc = SampleSpec {
+		rate : 48000,
+		channels : 2,
+		format : SampleFormat::I16,
+		big_endian : false,
+	}.audio_description().unwrap();
+	desc.validate().unwrap();
+	aac_desc().validate().unwrap();
+
+	let mut invalid = desc.clone();
+	invalid.sample_rate = 0.0;
+	match invalid.validate() {
+		Err(CafError::InvalidAudioDescription(_)) => (),
+		r => panic!("expected an invalid description error, got {:?}", r),
+	}
+	let mut invalid = desc.clone();
+	invalid.sample_rate = f64::NAN;
+	assert!(invalid.validate().is_err());
+	let mut invalid = desc.clone();
+	invalid.channels_per_frame = 0;
+	assert!(invalid.validate().is_err());
+	// Only allowed for VBR formats
+	let mut invalid = desc.clone();
+	invalid.bytes_per_packet = 0;
+	assert!(invalid.validate().is_err());
+
+	assert_eq!(CafError::InvalidAudioDescription("x").to_string(), "x");
+}
+
+#[test]
+fn clone_chunk() {
+	let encoded = encode_chunk(&CafChunk::Desc(aac_desc())).unwrap();
+	let ch = decode_chunk(ChunkType::AudioDescription, encoded).unwrap();
+	match (ch.clone(), ch) {
+		(CafChunk::Desc(a), CafChunk::Desc(b)) => {
+			assert_eq!(a.sample_rate, b.sample_rate);
+			assert_eq!(a.channels_per_frame, b.channels_per_frame);
+		},
+		_ => panic!("expected description chunks"),
+	}
+}
+
+#[test]
+fn chunk_equality() {
+	let encoded = encode_chunk(&CafChunk::Desc(aac_desc())).unwrap();
+	let ch = decode_chunk(ChunkType::AudioDescription, encoded).unwrap();
+	assert_eq!(ch, CafChunk::Desc(aac_desc()));
+	let mut other = aac_desc();
+	other.channels_per_frame = 1;
+	assert!(ch != CafChunk::Desc(other));
+	assert!(ch != CafChunk::MagicCookie(Vec::new()));
+	// NaN sample rates are never equal
+	let mut nan = aac_desc();
+	nan.sample_rate = f64::NAN;
+	assert!(nan != nan.clone());
+}
+
+#[test]
+fn user_defined_chunk() {
+	let mut body = (0 .. 16).collect::<Vec<u8>>();
+	body.extend_from_slice(b"payload");
+	let ch = decode_chunk(ChunkType::UserDefined, body.clone()).unwrap();
+	match ch {
+		CafChunk::UserDefined { ref uuid, ref data } => {
+			assert_eq!(uuid[15], 15);
+			assert_eq!(&data[..], b"payload");
+		},
+		_ => panic!("expected a user defined chunk"),
+	}
+	assert_eq!(ch.get_type(), ChunkType::UserDefined);
+	assert_eq!(encode_chunk(&ch).unwrap(), body);
+	// Too short for the UUID
+	assert!(decode_chunk(ChunkType::UserDefined, vec![0; 15]).is_err());
+}
+
+#[test]
+fn unique_material_identifier_chunk() {
+	let body = (0 .. 64).collect::<Vec<u8>>();
+	let ch = decode_chunk(ChunkType::UniqueMaterialIdentifier, body.clone()).unwrap();
+	assert_eq!(ch, CafChunk::UniqueMaterialIdentifier(body.clone()));
+	assert_eq!(ch.get_type(), ChunkType::UniqueMaterialIdentifier);
+	assert_eq!(encode_chunk(&ch).unwrap(), body);
+	assert!(decode_chunk(ChunkType::UniqueMaterialIdentifier, vec![0; 63]).is_err());
+}
+
+#[test]
+fn edit_comments_chunk() {
+	// Same layout as the info chunk
+	let body = vec![0, 0, 0, 2, b't', b'i', b'm', b'e', 0, b'c', b'u', b't', 0,
+		b'w', b'h', b'o', 0, b'm', b'e', 0];
+	let ch = decode_chunk(ChunkType::EditComments, body.clone()).unwrap();
+	assert_eq!(ch, CafChunk::EditComments(vec![("time".to_string(), "cut".to_string()),
+		("who".to_string(), "me".to_string())]));
+	assert_eq!(ch.get_type(), ChunkType::EditComments);
+	assert_eq!(encode_chunk(&ch).unwrap(), body);
+	assert!(can_decode_chunk_type(ChunkType::EditComments));
+}
+
+#[test]
+fn bytes_per_frame() {
+	let desc = AudioDescription::pcm_s16le(44100.0, 2);
+	assert_eq!(desc.bytes_per_frame(), Some(4));
+	assert!(!desc.is_compressed());
+	// Padded samples
+	let mut padded = desc.clone();
+	padded.bytes_per_packet = 8;
+	padded.bits_per_channel = 24;
+	assert_eq!(padded.bytes_per_frame(), Some(8));
+	let mut no_bytes_per_packet = desc.clone();
+	no_bytes_per_packet.bytes_per_packet = 0;
+	assert_eq!(no_bytes_per_packet.bytes_per_frame(), Some(4));
+	let mut multi_frame = desc.clone();
+	multi_frame.frames_per_packet = 2;
+	assert!(multi_frame.is_compressed());
+
+	assert!(aac_desc().is_compressed());
+	assert_eq!(aac_desc().bytes_per_frame(), None);
+}
+
+/// Asserts that decoding fails with an IO error instead of aborting
+fn assert_decode_io_error(ch_type :ChunkType, body :Vec<u8>) {
+	match decode_chunk(ch_type, body) {
+		Err(CafError::Io(_)) => (),
+		r => panic!("expected an IO error for {:?}, got {:?}", ch_type, r),
+	}
+}
+
+#[test]
+fn huge_marker_count() {
+	assert_decode_io_error(ChunkType::Marker, vec![0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn huge_entry_counts() {
+	// Negative packet count
+	let mut pakt = vec![0xff; 8];
+	pakt.extend_from_slice(&[0; 16]);
+	match decode_chunk(ChunkType::PacketTable, pakt) {
+		Err(CafError::Io(ref e)) if e.kind() == ErrorKind::InvalidData => (),
+		r => panic!("expected an invalid data error, got {:?}", r),
+	}
+	let mut pakt = vec![0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+	pakt.extend_from_slice(&[0; 16]);
+	assert_decode_io_error(ChunkType::PacketTable, pakt);
+	assert_decode_io_error(ChunkType::ChannelLayout,
+		vec![0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn huge_string_count() {
+	assert_decode_io_error(ChunkType::Strings, vec![0xff, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn huge_region_counts() {
+	assert_decode_io_error(ChunkType::Region, vec![0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+	// One region with a huge marker count
+	assert_decode_io_error(ChunkType::Region, vec![0, 0, 0, 0, 0, 0, 0, 1,
+		0, 0, 0, 7, 0, 0, 0, 3, 0xff, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn huge_entry_list_counts() {
+	assert_decode_io_error(ChunkType::Info, vec![0xff, 0xff, 0xff, 0xff]);
+	assert_decode_io_error(ChunkType::EditComments, vec![0xff, 0xff, 0xff, 0xff, b'a', 0]);
+}
@@ -0,0 +1,141 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+/*!
+Helpers to build CAF files in memory
+*/
+
+#![allow(dead_code)]
+
+fn be_u32(v :u32) -> [u8; 4] {
+	[(v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]
+}
+
+fn be_u64(v :u64) -> [u8; 8] {
+	let mut res = [0; 8];
+	for i in 0 .. 8 {
+		res[i] = (v >> (56 - 8 * i)) as u8;
+	}
+	res
+}
+
+/// A chunk with the given body, and the body's length as size
+pub fn chunk(ch_type :&[u8; 4], body :&[u8]) -> Vec<u8> {
+	chunk_sized(ch_type, body.len() as i64, body)
+}
+
+/// A chunk with the given body and the given size in the header
+pub fn chunk_sized(ch_type :&[u8; 4], size :i64, body :&[u8]) -> Vec<u8> {
+	let mut res = ch_type.to_vec();
+	res.extend_from_slice(&be_u64(size as u64));
+	res.extend_from_slice(body);
+	res
+}
+
+pub fn desc(sample_rate :f64, format :&[u8; 4], flags :u32, bytes_per_packet :u32,
+		frames_per_packet :u32, channels :u32, bits :u32) -> Vec<u8> {
+	let mut body = be_u64(sample_rate.to_bits()).to_vec();
+	body.extend_from_slice(format);
+	for v in [flags, bytes_per_packet, frames_per_packet, channels, bits].iter() {
+		body.extend_from_slice(&be_u32(*v));
+	}
+	chunk(b"desc", &body)
+}
+
+pub fn vlq(mut v :u64) -> Vec<u8> {
+	let mut res = vec![(v & 127) as u8];
+	v >>= 7;
+	while v > 0 {
+		res.insert(0, (v & 127) as u8 | 128);
+		v >>= 7;
+	}
+	res
+}
+
+pub fn pakt(valid :i64, priming :i32, remainder :i32, lengths :&[u64]) -> Vec<u8> {
+	let mut body = be_u64(lengths.len() as u64).to_vec();
+	body.extend_from_slice(&be_u64(valid as u64));
+	body.extend_from_slice(&be_u32(priming as u32));
+	body.extend_from_slice(&be_u32(remainder as u32));
+	for l in lengths.iter() {
+		body.extend_from_slice(&vlq(*l));
+	}
+	chunk(b"pakt", &body)
+}
+
+pub fn data(edit_count :u32, payload :&[u8]) -> Vec<u8> {
+	let mut body = be_u32(edit_count).to_vec();
+	body.extend_from_slice(payload);
+	chunk(b"data", &body)
+}
+
+/// An audio data chunk with unspecified (-1) size
+pub fn data_unsized(edit_count :u32, payload :&[u8]) -> Vec<u8> {
+	let mut body = be_u32(edit_count).to_vec();
+	body.extend_from_slice(payload);
+	chunk_sized(b"data", -1, &body)
+}
+
+pub fn info(entries :&[(&str, &str)]) -> Vec<u8> {
+	let mut body = be_u32(entries.len() as u32).to_vec();
+	for &(key, val) in entries.iter() {
+		body.extend_from_slice(key.as_bytes());
+		body.push(0);
+		body.extend_from_slice(val.as_bytes());
+		body.push(0);
+	}
+	chunk(b"info", &body)
+}
+
+/// A file made of the CAF header and the given chunks
+pub fn file(chunks :&[Vec<u8>]) -> Vec<u8> {
+	let mut res = b"caff\x00\x01\x00\x00".to_vec();
+	for c in chunks.iter() {
+		res.extend_from_slice(c);
+	}
+	res
+}
+
+/// Stereo 16 bit little endian samples, the value of each is its index
+pub fn pcm_payload(frames :usize) -> Vec<u8> {
+	let mut payload = Vec::new();
+	for i in 0 .. frames * 2 {
+		payload.push(i as u8);
+		payload.push((i >> 8) as u8);
+	}
+	payload
+}
+
+/// Stereo 16 bit little endian PCM with the given number of frames
+///
+/// The value of each sample is its index. Has an info chunk
+/// with a single entry between the description and the audio data.
+pub fn pcm_file(frames :usize) -> Vec<u8> {
+	file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16),
+		info(&[("title", "x")]), data(0, &pcm_payload(frames))])
+}
+
+/// Like `pcm_file`, but the audio data chunk has an unspecified size
+pub fn pcm_file_unsized(frames :usize) -> Vec<u8> {
+	file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16),
+		info(&[("title", "x")]), data_unsized(0, &pcm_payload(frames))])
+}
+
+/// AAC file with the given packet lengths
+///
+/// Packet `i` is filled with the byte `i`. The packet table has 2112
+/// priming and 100 remainder frames.
+pub fn vbr_file(lengths :&[u64]) -> Vec<u8> {
+	let mut payload = Vec::new();
+	for (i, l) in lengths.iter().enumerate() {
+		payload.extend(::std::iter::repeat(i as u8).take(*l as usize));
+	}
+	let valid = lengths.len() as i64 * 1024 - 2112 - 100;
+	file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0),
+		pakt(valid, 2112, 100, lengths), data(1, &payload)])
+}
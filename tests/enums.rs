@@ -0,0 +1,122 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+extern crate caf;
+
+use std::collections::HashSet;
+use caf::{fourcc, ChunkType, FormatType, ChannelLayoutTag, ChannelLabel};
+use caf::chunks::{ChannelLayout, CafChunk, decode_chunk};
+
+#[test]
+fn chunk_type_fourcc_round_trip() {
+	let fourccs = [fourcc::AUDIO_DESCRIPTION, fourcc::AUDIO_DATA,
+		fourcc::PACKET_TABLE, fourcc::INFO, fourcc::MARKER,
+		0, 0x41424344, u32::max_value()];
+	for &v in fourccs.iter() {
+		assert_eq!(u32::from(ChunkType::from(v)), v);
+	}
+}
+
+fn layout_tag(channel_layout_tag :u32) -> ChannelLayoutTag {
+	ChannelLayout {
+		channel_layout_tag : channel_layout_tag,
+		channel_bitmap : 0,
+		channel_descriptions : Vec::new(),
+	}.layout_tag()
+}
+
+#[test]
+fn channel_layout_tags() {
+	assert_eq!(layout_tag((100 << 16) | 1), ChannelLayoutTag::Mono);
+	assert_eq!(layout_tag((101 << 16) | 2), ChannelLayoutTag::Stereo);
+	assert_eq!(layout_tag((121 << 16) | 6), ChannelLayoutTag::Mpeg51A);
+	assert_eq!(layout_tag((147 << 16) | 12), ChannelLayoutTag::DiscreteInOrder(12));
+	assert_eq!(layout_tag(0xffff0003), ChannelLayoutTag::Unknown(3));
+	assert_eq!(layout_tag(0), ChannelLayoutTag::UseChannelDescriptions);
+	assert_eq!(layout_tag(12345), ChannelLayoutTag::Other(12345));
+	let tags = [0, 1 << 16, (100 << 16) | 1, (130 << 16) | 8, (147 << 16) | 3, 0xffff0002, 999];
+	for &v in tags.iter() {
+		assert_eq!(u32::from(ChannelLayoutTag::from(v)), v);
+	}
+}
+
+#[test]
+fn channel_labels() {
+	// Three channel descriptions
+	let mut body = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3];
+	for &label in [1u32, 4, 0x10005].iter() {
+		body.extend_from_slice(&label.to_be_bytes());
+		body.extend_from_slice(&[0; 16]);
+	}
+	let layout = match decode_chunk(ChunkType::ChannelLayout, body).unwrap() {
+		CafChunk::ChanLayout(layout) => layout,
+		_ => panic!("expected a channel layout chunk"),
+	};
+	let labels = layout.channel_descriptions.iter()
+		.map(|d| d.label()).collect::<Vec<_>>();
+	assert_eq!(labels, vec![ChannelLabel::Left, ChannelLabel::LfeScreen,
+		ChannelLabel::DiscreteNumbered(5)]);
+	for &v in [0, 3, 100, 400, 0x10002, 0xffffffff, 77777].iter() {
+		assert_eq!(u32::from(ChannelLabel::from(v)), v);
+	}
+}
+
+#[test]
+fn chunk_type_hash() {
+	let set = vec![ChunkType::Info, ChunkType::AudioData, ChunkType::Info, ChunkType::Other(5)]
+		.into_iter().collect::<HashSet<_>>();
+	assert_eq!(set.len(), 3);
+	assert!(set.contains(&ChunkType::Other(5)));
+}
+
+#[test]
+fn format_type_fourcc() {
+	assert_eq!(&FormatType::LinearPcm.fourcc_str(), b"lpcm");
+	assert_eq!(&FormatType::Mpeg4Aac.fourcc_str(), b"aac ");
+	let fourccs = [fourcc::LINEAR_PCM, fourcc::AAPL_LOSSLESS, fourcc::U_LAW, 0x12345678];
+	for &v in fourccs.iter() {
+		let format = FormatType::from(v);
+		let copy = format;
+		assert_eq!(copy, format);
+		assert_eq!(u32::from(format), v);
+		assert_eq!(FormatType::from(u32::from_be_bytes(format.fourcc_str())), format);
+	}
+}
+
+#[test]
+fn fourcc_display() {
+	assert_eq!(ChunkType::Other(0x6d79636b).to_string(), "myck");
+	// Not printable
+	assert_eq!(ChunkType::Other(0x01020304).to_string(), "0x01020304");
+	assert_eq!(FormatType::Mpeg4Aac.to_string(), "aac ");
+	assert_eq!(FormatType::Other(0x6f707573).to_string(), "opus");
+}
+
+#[test]
+fn chunk_type_names() {
+	let names = [
+		(b"desc", "Audio Description"), (b"data", "Audio Data"),
+		(b"pakt", "Packet Table"), (b"chan", "Channel Layout"),
+		(b"kuki", "Magic Cookie"), (b"strg", "Strings"),
+		(b"mark", "Marker"), (b"regn", "Region"),
+		(b"inst", "Instrument"), (b"midi", "MIDI"),
+		(b"ovvw", "Overview"), (b"peak", "Peak"),
+		(b"edct", "Edit Comments"), (b"info", "Information"),
+		(b"umid", "Unique Material Identifier"), (b"uuid", "User-Defined"),
+		(b"free", "Free"),
+	];
+	for &(fourcc, name) in names.iter() {
+		let ch_type = ChunkType::from(u32::from_be_bytes(*fourcc));
+		if let ChunkType::Other(_) = ch_type {
+			panic!("{} is not known", name);
+		}
+		assert_eq!(ch_type.to_string(), name);
+	}
+	// Debug output is unaffected
+	assert_eq!(format!("{:?}", ChunkType::Midi), "Midi");
+}
@@ -0,0 +1,32 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+extern crate caf;
+
+use caf::{CafError, ChunkType};
+
+#[test]
+fn display() {
+	assert_eq!(CafError::NotCaf.to_string(),
+		"The given stream doesn't start with a CAF header");
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn std_error() {
+	let err :Box<dyn std::error::Error> = Box::new(CafError::NotCaf);
+	assert_eq!(err.to_string(), "The given stream doesn't start with a CAF header");
+}
+
+#[test]
+fn display_values() {
+	assert_eq!(CafError::InvalidChunkSize(-5).to_string(),
+		"Encountered a chunk with a negative size: -5");
+	assert_eq!(CafError::MissingChunk(ChunkType::Info).to_string(),
+		"A required chunk couldn't be found: Information");
+}
@@ -0,0 +1,91 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+// Only uses the `caf::io` traits, so it works with and without `std`
+
+extern crate caf;
+
+mod common;
+
+use caf::io::{self, Cursor, Read, Seek, SeekFrom, ErrorKind};
+use caf::{CafPacketReader, CafChunkReader, ChunkType};
+use common::*;
+
+/// A source over a byte slice, like an embedded player might provide
+struct SliceSource<'a> {
+	data :&'a [u8],
+	pos :usize,
+}
+
+impl<'a> Read for SliceSource<'a> {
+	fn read(&mut self, buf :&mut [u8]) -> io::Result<usize> {
+		let rest = &self.data[::std::cmp::min(self.pos, self.data.len())..];
+		let n = ::std::cmp::min(rest.len(), buf.len());
+		buf[..n].copy_from_slice(&rest[..n]);
+		self.pos += n;
+		Ok(n)
+	}
+}
+
+impl<'a> Seek for SliceSource<'a> {
+	fn seek(&mut self, pos :SeekFrom) -> io::Result<u64> {
+		let new_pos = match pos {
+			SeekFrom::Start(v) => v as i64,
+			SeekFrom::End(v) => self.data.len() as i64 + v,
+			SeekFrom::Current(v) => self.pos as i64 + v,
+		};
+		if new_pos < 0 {
+			return Err(io::Error::new(ErrorKind::InvalidInput, "negative position"));
+		}
+		self.pos = new_pos as usize;
+		Ok(new_pos as u64)
+	}
+}
+
+#[test]
+fn byte_slice_source() {
+	let f = vbr_file(&[3, 5, 7]);
+	let expected = vec![vec![0; 3], vec![1; 5], vec![2; 7]];
+
+	let mut rdr = CafPacketReader::from_bytes(&f, vec![ChunkType::Info]).unwrap();
+	let packets = rdr.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(packets, expected);
+
+	let rdr = CafPacketReader::new(SliceSource { data : &f, pos : 0 }, vec![]).unwrap();
+	let packets = rdr.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(packets, expected);
+
+	let f = pcm_file(3);
+	let rdr = CafPacketReader::new(SliceSource { data : &f, pos : 0 }, vec![]).unwrap();
+	let samples = rdr.pcm_samples_iter().unwrap()
+		.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(samples, vec![0, 1, 2, 3, 4, 5]);
+
+	let rdr = CafChunkReader::new(Cursor::new(&f[..])).unwrap();
+	assert_eq!(rdr.chunk_headers().count(), 3);
+}
+
+#[test]
+fn cursor_seek() {
+	let data = [1, 2, 3, 4];
+	let mut cursor = Cursor::new(&data[..]);
+	assert_eq!(cursor.seek(SeekFrom::End(-1)).unwrap(), 3);
+	let mut buf = [0; 2];
+	assert_eq!(cursor.read(&mut buf).unwrap(), 1);
+	assert_eq!(buf[0], 4);
+	assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+	assert!(cursor.seek(SeekFrom::Current(-5)).is_err());
+	assert_eq!(cursor.seek(SeekFrom::Current(-4)).unwrap(), 0);
+	// Seeking past the end is allowed, reading there yields nothing
+	assert_eq!(cursor.seek(SeekFrom::Start(10)).unwrap(), 10);
+	assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+	match cursor.read_exact(&mut buf) {
+		Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => (),
+		v => panic!("unexpected result {:?}", v),
+	}
+}
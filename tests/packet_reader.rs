@@ -0,0 +1,785 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+extern crate caf;
+
+mod common;
+
+use std::time::Duration;
+use caf::io::{self, Cursor, Read};
+use caf::{CafPacketReader, CafPacketReaderBuilder, PacketSource, ChunkType, CafError};
+use caf::FormatType;
+use caf::chunks::{AudioDescription, PacketTable, parse_alac_cookie};
+use common::*;
+
+fn read_all(rdr :&mut CafPacketReader<Cursor<Vec<u8>>>) -> Vec<Vec<u8>> {
+	let mut res = Vec::new();
+	while let Some(packet) = rdr.next_packet().unwrap() {
+		res.push(packet);
+	}
+	res
+}
+
+#[test]
+fn reencode_round_trip() {
+	let lengths = [3, 5, 200, 70000];
+	let mut payload = Vec::new();
+	for (i, l) in lengths.iter().enumerate() {
+		payload.extend(::std::iter::repeat(i as u8).take(*l as usize));
+	}
+	let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0),
+		info(&[("a", "b")]), chunk(b"free", &[0; 5]),
+		pakt(4 * 1024 - 2112 - 100, 2112, 100, &lengths), data(1, &payload)]);
+
+	let mut rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![ChunkType::Info]).unwrap();
+	// Rewinding is part of reencode
+	rdr.next_packet().unwrap();
+	let encoded = rdr.reencode().unwrap();
+
+	let mut rdr = CafPacketReader::new(Cursor::new(f), vec![ChunkType::Info]).unwrap();
+	let mut rdr_enc = CafPacketReader::new(Cursor::new(encoded), vec![ChunkType::Info]).unwrap();
+	assert_eq!(rdr_enc.chunks, rdr.chunks);
+	assert_eq!(rdr_enc.packet_table, rdr.packet_table);
+	assert_eq!(rdr_enc.edit_count, 1);
+	assert_eq!(read_all(&mut rdr_enc), read_all(&mut rdr));
+
+	// Files without unknown chunks are reproduced exactly
+	let f = pcm_file(5);
+	let mut rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![ChunkType::Info]).unwrap();
+	assert_eq!(rdr.reencode().unwrap(), f);
+}
+
+#[test]
+fn audio_chunk_without_edit_count() {
+	use caf::io::ErrorKind;
+	// The data chunk is too small to hold the edit count
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16),
+		chunk(b"data", &[0, 0]), chunk(b"free", &[0; 8])]);
+	let is_invalid_data = |res :Result<_, CafError>| match res {
+		Err(CafError::Io(ref e)) => e.kind() == ErrorKind::InvalidData,
+		_ => false,
+	};
+	assert!(is_invalid_data(CafPacketReader::new(Cursor::new(f.clone()), vec![]).map(|_| ())));
+	assert!(is_invalid_data(CafPacketReader::new_lazy(Cursor::new(f.clone()), vec![]).map(|_| ())));
+	assert!(is_invalid_data(CafPacketReader::new_sequential(Cursor::new(f), vec![]).map(|_| ())));
+}
+
+#[test]
+fn seek_to_packet_forward_and_backward() {
+	let f = vbr_file(&[3, 5, 7, 2]);
+	let mut rdrs = vec![
+		CafPacketReader::new(Cursor::new(f.clone()), vec![]).unwrap(),
+		CafPacketReader::new_lazy(Cursor::new(f), vec![]).unwrap(),
+	];
+	for rdr in rdrs.iter_mut() {
+		rdr.seek_to_packet(2).unwrap();
+		assert_eq!(rdr.next_packet().unwrap().unwrap(), vec![2; 7]);
+		rdr.seek_to_packet(1).unwrap();
+		assert_eq!(rdr.next_packet().unwrap().unwrap(), vec![1; 5]);
+		rdr.seek_to_packet(0).unwrap();
+		assert_eq!(rdr.next_packet().unwrap().unwrap(), vec![0; 3]);
+		rdr.seek_to_packet(3).unwrap();
+		assert_eq!(rdr.next_packet().unwrap().unwrap(), vec![3; 2]);
+		// Seeking to the packet count puts the reader after the last packet
+		rdr.seek_to_packet(4).unwrap();
+		assert!(rdr.next_packet().unwrap().is_none());
+		assert!(rdr.seek_to_packet(5).is_err());
+		rdr.seek_to_packet(1).unwrap();
+		assert_eq!(rdr.next_packet().unwrap().unwrap(), vec![1; 5]);
+	}
+}
+
+#[test]
+fn seek_to_packet_huge_lengths() {
+	let max = i64::max_value() as u64;
+	for lengths in [[max, max, max], [1 << 63, 1, 1], [u64::max_value(), 1, 1]].iter() {
+		let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0),
+			pakt(3 * 1024, 0, 0, lengths), data(0, &[1, 2, 3])]);
+		let mut rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+		assert!(rdr.seek_to_packet(3).is_err());
+		assert!(rdr.seek_to_packet(1).is_err());
+		// The reader is still at the first packet
+		assert_eq!(rdr.get_packet_idx(), 0);
+	}
+}
+
+#[test]
+fn byte_offset_of_frame_huge_lengths() {
+	let max = i64::max_value() as u64;
+	let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0),
+		pakt(3 * 1024, 0, 0, &[max, max, max]), data(0, &[1, 2, 3])]);
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	assert!(rdr.byte_offset_of_frame(0).is_some());
+	assert_eq!(rdr.byte_offset_of_frame(1024), Some(rdr.audio_data_offset() + max));
+	assert_eq!(rdr.byte_offset_of_frame(2 * 1024), None);
+}
+
+#[test]
+fn seek_to_byte_huge_lengths() {
+	let max = i64::max_value() as u64;
+	let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0),
+		pakt(3 * 1024, 0, 0, &[max, max, max]), data(0, &[1, 2, 3])]);
+	let mut rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	rdr.seek_to_byte(5).unwrap();
+	assert_eq!(rdr.get_packet_idx(), 0);
+	assert!(rdr.seek_to_byte(u64::max_value()).is_err());
+	assert_eq!(rdr.get_packet_idx(), 0);
+}
+
+#[test]
+fn unsized_audio_chunk_ends_at_eof() {
+	let f = pcm_file_unsized(5);
+	let mut rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![]).unwrap();
+	assert_eq!(rdr.audio_data_len(), -1);
+	assert_eq!(read_all(&mut rdr).len(), 5);
+	assert_eq!(rdr.next_packet().unwrap(), None);
+	assert_eq!(rdr.audio_data_len(), 4 + 5 * 4);
+	assert_eq!(rdr.get_packet_count(), Some(5));
+	assert!(!rdr.was_truncated());
+
+	let mut rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![]).unwrap();
+	let mut n = 0;
+	while let Some(packet) = rdr.next_packet_ref().unwrap() {
+		assert_eq!(packet.len(), 4);
+		n += 1;
+	}
+	assert_eq!(n, 5);
+
+	let rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![]).unwrap();
+	let packets = rdr.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(packets.concat(), pcm_payload(5));
+
+	let rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![]).unwrap();
+	let packets = rdr.into_packet_iter().collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(packets.len(), 5);
+
+	let mut rdr = CafPacketReader::new(Cursor::new(f), vec![ChunkType::Info]).unwrap();
+	let encoded = rdr.reencode().unwrap();
+	let mut rdr_enc = CafPacketReader::new(Cursor::new(encoded), vec![]).unwrap();
+	assert_eq!(read_all(&mut rdr_enc).concat(), pcm_payload(5));
+}
+
+#[test]
+fn unsized_audio_chunk_partial_packet() {
+	let mut f = pcm_file_unsized(5);
+	f.pop();
+	let mut rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	for _ in 0 .. 4 {
+		rdr.next_packet().unwrap().unwrap();
+	}
+	match rdr.next_packet() {
+		Err(CafError::Io(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+		v => panic!("unexpected result {:?}", v),
+	}
+}
+
+#[test]
+fn builder_optional_chunks() {
+	// The file has no channel layout chunk
+	let rdr = CafPacketReaderBuilder::new()
+		.keep_channel_layout()
+		.keep_magic_cookie()
+		.build(Cursor::new(pcm_file(2))).unwrap();
+	assert!(rdr.chunks.is_empty());
+
+	// Optional chunks after the audio data are still found
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16),
+		data(0, &pcm_payload(2)), chunk(b"kuki", &[1, 2, 3])]);
+	let mut rdr = CafPacketReaderBuilder::new()
+		.keep_channel_layout()
+		.keep_magic_cookie()
+		.build(Cursor::new(f)).unwrap();
+	assert_eq!(rdr.chunks.len(), 1);
+	assert_eq!(rdr.chunks[0].get_type(), ChunkType::MagicCookie);
+	assert_eq!(read_all(&mut rdr).concat(), pcm_payload(2));
+}
+
+#[test]
+fn builder_lazy_packet_table() {
+	let f = vbr_file(&[3, 5, 7]);
+	let mut rdr = CafPacketReaderBuilder::new()
+		.lazy_packet_table()
+		.build(Cursor::new(f.clone())).unwrap();
+	assert!(rdr.packet_table.as_ref().unwrap().lengths.is_empty());
+	let mut rdr_eager = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	assert_eq!(read_all(&mut rdr), read_all(&mut rdr_eager));
+}
+
+#[test]
+fn info_is_optional() {
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16),
+		data(0, &pcm_payload(2))]);
+	let mut rdr = CafPacketReader::new(Cursor::new(f.clone()),
+		vec![ChunkType::Info]).unwrap();
+	assert!(rdr.all_info().is_empty());
+	assert_eq!(read_all(&mut rdr).concat(), pcm_payload(2));
+	let rdr = CafPacketReader::new_lazy(Cursor::new(f),
+		vec![ChunkType::Info]).unwrap();
+	assert!(rdr.all_info().is_empty());
+
+	// Other chunk types are still required
+	let f = pcm_file(2);
+	match CafPacketReader::new(Cursor::new(f.clone()), vec![ChunkType::MagicCookie]) {
+		Err(CafError::MissingChunk(ChunkType::MagicCookie)) => (),
+		v => panic!("unexpected result {:?}", v.map(|_| ())),
+	}
+	let rdr = CafPacketReader::new(Cursor::new(f),
+		vec![ChunkType::Info]).unwrap();
+	assert_eq!(rdr.all_info(), vec![("title".to_string(), "x".to_string())]);
+}
+
+#[test]
+fn sized_audio_chunk_at_eof() {
+	// The sized audio data chunk is the last one, reaching up to the EOF
+	let mut rdr = CafPacketReader::new(Cursor::new(vbr_file(&[3, 5, 7])), vec![]).unwrap();
+	let packets = read_all(&mut rdr);
+	assert_eq!(packets.last(), Some(&vec![2; 7]));
+	assert!(!rdr.was_truncated());
+
+	let mut rdr = CafPacketReader::new(Cursor::new(pcm_file(3)), vec![]).unwrap();
+	let packets = read_all(&mut rdr);
+	assert_eq!(packets.len(), 3);
+	assert_eq!(packets.last(), Some(&vec![4, 0, 5, 0]));
+	assert!(!rdr.was_truncated());
+}
+
+fn count_packets(src :&mut dyn PacketSource) -> usize {
+	let mut n = 0;
+	while let Some(_) = src.next_packet().unwrap() {
+		n += 1;
+	}
+	n
+}
+
+#[test]
+fn packet_source_trait_object() {
+	let mut rdr = CafPacketReader::new(Cursor::new(vbr_file(&[3, 5, 7])), vec![]).unwrap();
+	assert_eq!(rdr.audio_description().channels_per_frame, 2);
+	assert_eq!(count_packets(&mut rdr), 3);
+	assert_eq!(count_packets(&mut rdr), 0);
+}
+
+#[test]
+fn lazy_packet_table_matches_eager() {
+	let lengths = (0 .. 3000).map(|i| (i * 37) % 300 + 1).collect::<Vec<u64>>();
+	let f = vbr_file(&lengths);
+	let mut rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![]).unwrap();
+	let eager = read_all(&mut rdr);
+	assert_eq!(eager.len(), 3000);
+
+	let mut rdr = CafPacketReader::new_lazy(Cursor::new(f), vec![]).unwrap();
+	assert_eq!(rdr.get_packet_count(), Some(3000));
+	assert!(rdr.packet_table.as_ref().unwrap().lengths.is_empty());
+	assert_eq!(read_all(&mut rdr), eager);
+
+	// The packet table comes after the audio data
+	let mut payload = Vec::new();
+	for (i, l) in lengths.iter().enumerate() {
+		payload.extend(::std::iter::repeat(i as u8).take(*l as usize));
+	}
+	let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0),
+		info(&[("a", "b")]), data(1, &payload), pakt(0, 0, 0, &lengths)]);
+	let mut rdr = CafPacketReader::new_lazy(Cursor::new(f), vec![ChunkType::Info]).unwrap();
+	assert_eq!(rdr.chunks.len(), 1);
+	let encoded = rdr.reencode().unwrap();
+	assert_eq!(rdr.reencode().unwrap(), encoded);
+	let mut rdr = CafPacketReader::new(Cursor::new(encoded), vec![]).unwrap();
+	assert_eq!(read_all(&mut rdr), eager);
+}
+
+/// A reader that doesn't implement `Seek`
+struct NoSeek(Cursor<Vec<u8>>);
+
+impl Read for NoSeek {
+	fn read(&mut self, buf :&mut [u8]) -> io::Result<usize> {
+		self.0.read(buf)
+	}
+}
+
+#[test]
+fn sequential_reader() {
+	let lengths = [3, 5, 1, 9];
+	let mut rdr = CafPacketReader::new_sequential(NoSeek(Cursor::new(vbr_file(&lengths))),
+		vec![]).unwrap();
+	let mut n = 0;
+	while let Some(packet) = rdr.next_packet().unwrap() {
+		assert_eq!(packet.len() as u64, lengths[n]);
+		n += 1;
+	}
+	assert_eq!(n, 4);
+
+	let rdr = CafPacketReader::new_sequential(NoSeek(Cursor::new(pcm_file(5))),
+		vec![ChunkType::Info]).unwrap();
+	assert_eq!(rdr.chunks.len(), 1);
+	let samples = rdr.pcm_samples_iter().unwrap()
+		.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(samples.len(), 10);
+
+	// Seeking forward works, seeking backward doesn't
+	let mut rdr = CafPacketReader::new_sequential(NoSeek(Cursor::new(vbr_file(&lengths))),
+		vec![]).unwrap();
+	rdr.seek_to_packet(2).unwrap();
+	assert_eq!(rdr.next_packet().unwrap().unwrap().len(), 1);
+	assert!(rdr.seek_to_packet(0).is_err());
+
+	// The audio description has to come before the audio data
+	let f = file(&[data(0, &[0; 4]), desc(44100.0, b"lpcm", 2, 4, 1, 2, 16)]);
+	match CafPacketReader::new_sequential(NoSeek(Cursor::new(f)), vec![]) {
+		Err(CafError::MissingChunk(ChunkType::AudioDescription)) => (),
+		v => panic!("unexpected result {:?}", v.map(|_| ())),
+	}
+}
+
+#[test]
+fn packet_range_for_time() {
+	// 100 packets with 1024 frames each at 44100 Hz
+	let rdr = CafPacketReader::new(Cursor::new(vbr_file(&[3; 100])), vec![]).unwrap();
+	assert_eq!(rdr.packet_range_for_time(0.0, 1024.0 / 44100.0), Some(0 .. 1));
+	assert_eq!(rdr.packet_range_for_time(0.5, 1.0), Some(21 .. 44));
+	assert_eq!(rdr.packet_range_for_time(0.5, 100.0), Some(21 .. 100));
+	assert_eq!(rdr.packet_range_for_time(10.0, 100.0), None);
+	assert_eq!(rdr.packet_range_for_time(-3.0, -1.0), None);
+}
+
+#[test]
+fn next_packet_ref() {
+	let mut rdr = CafPacketReader::new(Cursor::new(vbr_file(&[3, 5, 2])), vec![]).unwrap();
+	assert_eq!(rdr.next_packet_ref().unwrap().unwrap(), &[0; 3][..]);
+	assert_eq!(rdr.next_packet_ref().unwrap().unwrap(), &[1; 5][..]);
+	assert_eq!(rdr.next_packet_ref().unwrap().unwrap(), &[2; 2][..]);
+	assert!(rdr.next_packet_ref().unwrap().is_none());
+}
+
+#[test]
+fn average_frames_per_packet() {
+	// No frames per packet in the description
+	let f = file(&[desc(44100.0, b"aac ", 0, 0, 0, 2, 0),
+		pakt(10 * 1024 - 2112 - 100, 2112, 100, &[3; 10]), data(1, &[0; 30])]);
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	let packet_table = rdr.packet_table.as_ref().unwrap();
+	assert_eq!(packet_table.average_frames_per_packet(), Some(1024.0));
+}
+
+#[test]
+fn audio_reader() {
+	let mut f = vbr_file(&[3, 5, 7]);
+	f.extend_from_slice(&info(&[("a", "b")]));
+	let mut rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![]).unwrap();
+	let packets = read_all(&mut rdr).concat();
+	let mut rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	let mut audio = Vec::new();
+	rdr.audio_reader().read_to_end(&mut audio).unwrap();
+	// Stops at the end of the audio data chunk
+	assert_eq!(audio, packets);
+}
+
+#[test]
+fn byte_offset_of_frame() {
+	let f = vbr_file(&[3, 5, 7]);
+	let start = (f.len() - 15) as u64;
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	assert_eq!(rdr.byte_offset_of_frame(0), Some(start));
+	assert_eq!(rdr.byte_offset_of_frame(1023), Some(start));
+	assert_eq!(rdr.byte_offset_of_frame(1024), Some(start + 3));
+	assert_eq!(rdr.byte_offset_of_frame(2048 + 5), Some(start + 8));
+	assert_eq!(rdr.byte_offset_of_frame(3072), None);
+
+	let f = pcm_file(10);
+	let start = (f.len() - 40) as u64;
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	assert_eq!(rdr.byte_offset_of_frame(7), Some(start + 28));
+	assert_eq!(rdr.byte_offset_of_frame(10), None);
+}
+
+#[test]
+fn compression_ratio() {
+	// 16 bit stereo ALAC, two packets of 4096 frames each
+	let f = file(&[desc(44100.0, b"alac", 1, 0, 4096, 2, 0),
+		pakt(8192, 0, 0, &[1000, 1000]), data(0, &[0; 2000])]);
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	let ratio = rdr.compression_ratio().unwrap();
+	assert!((ratio - 8192.0 * 4.0 / 2000.0).abs() < 1e-9);
+	// Bit depth of AAC is unknown
+	let rdr = CafPacketReader::new(Cursor::new(vbr_file(&[10, 20])), vec![]).unwrap();
+	assert_eq!(rdr.compression_ratio(), None);
+	let rdr = CafPacketReader::new(Cursor::new(pcm_file(10)), vec![]).unwrap();
+	assert_eq!(rdr.compression_ratio(), Some(1.0));
+}
+
+fn aac_desc() -> AudioDescription {
+	AudioDescription {
+		sample_rate : 44100.0,
+		format_id : FormatType::Mpeg4Aac,
+		format_flags : 0,
+		bytes_per_packet : 0,
+		frames_per_packet : 1024,
+		channels_per_frame : 2,
+		bits_per_channel : 0,
+	}
+}
+
+#[test]
+fn from_parts() {
+	let table = PacketTable {
+		num_valid_frames : 2048,
+		num_priming_frames : 0,
+		num_remainder_frames : 0,
+		lengths : vec![2, 3],
+	};
+	// Two bytes of something else, then the body of the audio data chunk
+	let mut body = vec![9, 9, 0, 0, 0, 7, 1, 1, 2, 2, 2];
+	let mut c = Cursor::new(body.clone());
+	c.set_position(2);
+	let mut rdr = CafPacketReader::from_parts(c, aac_desc(), Some(table.clone()), 9).unwrap();
+	assert_eq!(rdr.edit_count, 7);
+	assert_eq!(read_all(&mut rdr), vec![vec![1, 1], vec![2, 2, 2]]);
+
+	// VBR formats need a packet table
+	assert!(CafPacketReader::from_parts(Cursor::new(body.clone()),
+		aac_desc(), None, 9).is_err());
+	// The chunk is too small for the packet table
+	assert!(CafPacketReader::from_parts(Cursor::new(body.clone()),
+		aac_desc(), Some(table.clone()), 8).is_err());
+	body.truncate(9);
+	assert!(CafPacketReader::from_parts(Cursor::new(body),
+		aac_desc(), Some(table), -1).is_ok());
+}
+
+#[test]
+fn duration_seconds() {
+	let rdr = CafPacketReader::new(Cursor::new(vbr_file(&[10; 10])), vec![]).unwrap();
+	let raw = rdr.duration_seconds().unwrap();
+	assert!((raw - 10240.0 / 44100.0).abs() < 1e-9);
+	// Priming and remainder frames are not played
+	assert!(rdr.playable_duration_seconds().unwrap() < raw);
+
+	let rdr = CafPacketReader::new(Cursor::new(pcm_file(441)), vec![]).unwrap();
+	assert_eq!(rdr.duration_seconds(), Some(0.01));
+	assert_eq!(rdr.playable_duration_seconds(), Some(0.01));
+}
+
+#[test]
+fn read_raw() {
+	let mut f = pcm_file(3);
+	f.extend_from_slice(&info(&[("x", "y")]));
+	let mut rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	let mut buf = [0; 100];
+	let mut total = 0;
+	loop {
+		let n = rdr.read_raw(&mut buf[total ..]).unwrap();
+		if n == 0 {
+			break;
+		}
+		total += n;
+	}
+	// Doesn't read into the info chunk after the audio data
+	assert_eq!(total, 12);
+	assert_eq!(&buf[.. 12], &pcm_payload(3)[..]);
+	assert_eq!(rdr.read_raw(&mut buf).unwrap(), 0);
+}
+
+#[test]
+fn packet_iterator() {
+	let lengths = [3, 5, 1, 9];
+	let rdr = CafPacketReader::new(Cursor::new(vbr_file(&lengths)), vec![]).unwrap();
+	let packets = rdr.collect::<Result<Vec<_>, _>>().unwrap();
+	let mut rdr = CafPacketReader::new(Cursor::new(vbr_file(&lengths)), vec![]).unwrap();
+	assert_eq!(packets.len(), 4);
+	assert_eq!(packets, read_all(&mut rdr));
+}
+
+#[test]
+fn was_truncated() {
+	// The packet table lists 15 bytes, but the chunk only has 10
+	let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0),
+		pakt(5120, 0, 0, &[3, 3, 3, 3, 3]), data(0, &[1; 10])]);
+	let mut rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	let mut n = 0;
+	while rdr.next_packet().unwrap().is_some() {
+		n += 1;
+		assert!(!rdr.was_truncated() || n == 3);
+	}
+	assert_eq!(n, 3);
+	assert!(rdr.was_truncated());
+	assert_eq!(rdr.get_packet_idx(), 3);
+
+	let mut rdr = CafPacketReader::new(Cursor::new(vbr_file(&[3, 4])), vec![]).unwrap();
+	read_all(&mut rdr);
+	assert!(!rdr.was_truncated());
+}
+
+#[test]
+fn set_frames_per_packet() {
+	let f = file(&[desc(1000.0, b"aac ", 0, 0, 0, 2, 0),
+		pakt(0, 0, 0, &[3, 3]), data(0, &[1; 6])]);
+	let mut rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	assert_eq!(rdr.duration_seconds(), Some(0.0));
+	assert_eq!(rdr.byte_offset_of_frame(10), None);
+	rdr.set_frames_per_packet(500);
+	assert_eq!(rdr.duration_seconds(), Some(1.0));
+	assert_eq!(rdr.packet_range_for_time(0.6, 0.7), Some(1 .. 2));
+	assert_eq!(rdr.next_packet().unwrap().unwrap().len(), 3);
+}
+
+#[test]
+fn duration() {
+	let rdr = CafPacketReader::new(Cursor::new(pcm_file(22050)), vec![]).unwrap();
+	assert_eq!(rdr.duration(), Some(Duration::from_millis(500)));
+	let rdr = CafPacketReader::new(Cursor::new(vbr_file(&[10; 100])), vec![]).unwrap();
+	let duration = rdr.duration().unwrap();
+	let secs = duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9;
+	assert!((secs - (100.0 * 1024.0 - 2112.0 - 100.0) / 44100.0).abs() < 1e-6);
+}
+
+#[test]
+fn seek_to_byte() {
+	let lengths = (0 .. 2500).map(|i| i % 5 + 1).collect::<Vec<u64>>();
+	for &lazy in [false, true].iter() {
+		let c = Cursor::new(vbr_file(&lengths));
+		let mut rdr = if lazy {
+			CafPacketReader::new_lazy(c, vec![])
+		} else {
+			CafPacketReader::new(c, vec![])
+		}.unwrap();
+		for &offs in [0, 1, 5, 6, 3000, 7000, 7499].iter() {
+			rdr.seek_to_byte(offs).unwrap();
+			// The packet containing the byte
+			let mut start = 0;
+			let mut idx = 0;
+			while start + lengths[idx] <= offs {
+				start += lengths[idx];
+				idx += 1;
+			}
+			assert_eq!(rdr.get_packet_idx(), idx);
+			assert_eq!(rdr.next_packet().unwrap().unwrap()[0], idx as u8);
+		}
+		rdr.seek_to_byte(1 << 40).unwrap();
+		assert_eq!(rdr.next_packet().unwrap(), None);
+	}
+
+	let mut rdr = CafPacketReader::new(Cursor::new(pcm_file(10)), vec![]).unwrap();
+	rdr.seek_to_byte(9).unwrap();
+	assert_eq!(rdr.get_packet_idx(), 2);
+	rdr.seek_to_byte(1000).unwrap();
+	assert_eq!(rdr.get_packet_idx(), 10);
+}
+
+#[test]
+fn current_frame() {
+	let mut rdr = CafPacketReader::new(Cursor::new(vbr_file(&[1, 2, 3])), vec![]).unwrap();
+	assert_eq!(rdr.current_frame(), Some(0));
+	rdr.next_packet().unwrap();
+	rdr.next_packet().unwrap();
+	assert_eq!(rdr.current_frame(), Some(2048));
+	// Unknown frames per packet
+	let f = file(&[desc(1000.0, b"aac ", 0, 0, 0, 2, 0),
+		pakt(0, 0, 0, &[3, 3]), data(0, &[1; 6])]);
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	assert_eq!(rdr.current_frame(), None);
+}
+
+#[test]
+fn seek_to_frame() {
+	let mut rdr = CafPacketReader::new(Cursor::new(pcm_file(10)), vec![]).unwrap();
+	assert_eq!(rdr.seek_to_frame(7).unwrap(), 7);
+	assert_eq!(rdr.next_packet().unwrap().unwrap(), vec![14, 0, 15, 0]);
+	assert_eq!(rdr.seek_to_frame(100).unwrap(), 10);
+	assert_eq!(rdr.next_packet().unwrap(), None);
+
+	// Lands on the start of the packet containing the frame
+	let f = file(&[desc(44100.0, b"alac", 1, 0, 4096, 2, 0),
+		pakt(8192 + 100, 0, 0, &[10, 20, 30]), data(0, &[0; 60])]);
+	let mut rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	assert_eq!(rdr.seek_to_frame(5000).unwrap(), 4096);
+	assert_eq!(rdr.next_packet().unwrap().unwrap().len(), 20);
+	assert_eq!(rdr.seek_to_frame(0).unwrap(), 0);
+	assert_eq!(rdr.next_packet().unwrap().unwrap().len(), 10);
+}
+
+#[test]
+fn next_packet_with_offset() {
+	let f = vbr_file(&[3, 5, 2]);
+	let mut rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![]).unwrap();
+	let (offs_0, packet_0) = rdr.next_packet_with_offset().unwrap().unwrap();
+	assert_eq!(&f[offs_0 as usize .. offs_0 as usize + 3], &packet_0[..]);
+	let (offs_1, packet_1) = rdr.next_packet_with_offset().unwrap().unwrap();
+	assert_eq!(offs_1, offs_0 + 3);
+	assert_eq!(packet_1, vec![1; 5]);
+	let (offs_2, _) = rdr.next_packet_with_offset().unwrap().unwrap();
+	assert_eq!(offs_2, offs_1 + 5);
+	assert!(rdr.next_packet_with_offset().unwrap().is_none());
+
+	let mut rdr = CafPacketReader::new_lazy(Cursor::new(f), vec![]).unwrap();
+	rdr.next_packet().unwrap();
+	assert_eq!(rdr.next_packet_with_offset().unwrap().unwrap().0, offs_1);
+}
+
+/// ALAC specific config: 4096 frames, 16 bit, stereo, 44100 Hz
+const ALAC_COOKIE :[u8; 24] = [0, 0, 0x10, 0, 0, 16, 40, 10, 14, 2, 0, 255,
+	0, 0, 0x20, 0x0c, 0, 0x0f, 0x42, 0x40, 0, 0, 0xac, 0x44];
+
+#[test]
+fn alac_magic_cookie() {
+	let config = parse_alac_cookie(&ALAC_COOKIE).unwrap();
+	assert_eq!(config.frame_length, 4096);
+	assert_eq!(config.bit_depth, 16);
+	assert_eq!(config.num_channels, 2);
+	assert_eq!(config.sample_rate, 44100);
+	assert_eq!(config.max_run, 255);
+	assert_eq!(config.pb, 40);
+	assert_eq!(config.avg_bit_rate, 1_000_000);
+
+	// Wrapped in an 'alac' atom
+	let mut wrapped = vec![0, 0, 0, 36];
+	wrapped.extend_from_slice(b"alac");
+	wrapped.extend_from_slice(&[0; 4]);
+	wrapped.extend_from_slice(&ALAC_COOKIE);
+	assert_eq!(parse_alac_cookie(&wrapped), Some(config.clone()));
+	// Preceded by a 'frma' atom
+	let mut with_frma = vec![0, 0, 0, 12];
+	with_frma.extend_from_slice(b"frmaalac");
+	with_frma.extend_from_slice(&wrapped);
+	assert_eq!(parse_alac_cookie(&with_frma), Some(config));
+	assert_eq!(parse_alac_cookie(&ALAC_COOKIE[.. 23]), None);
+
+	let f = file(&[desc(44100.0, b"alac", 1, 0, 4096, 2, 0), chunk(b"kuki", &ALAC_COOKIE),
+		pakt(4096, 0, 0, &[3]), data(0, &[1, 2, 3])]);
+	let rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![ChunkType::MagicCookie]).unwrap();
+	assert_eq!(rdr.magic_cookie_for(FormatType::AppleLossless), Some(&ALAC_COOKIE[..]));
+	assert_eq!(rdr.magic_cookie_for(FormatType::LinearPcm), None);
+	// Not kept
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	assert_eq!(rdr.magic_cookie_for(FormatType::AppleLossless), None);
+}
+
+#[test]
+fn from_bytes() {
+	let f = pcm_file(3);
+	let mut rdr = CafPacketReader::from_bytes(&f, vec![ChunkType::Info]).unwrap();
+	assert_eq!(rdr.chunks.len(), 1);
+	assert_eq!(rdr.next_packet().unwrap().unwrap(), vec![0, 0, 1, 0]);
+	assert_eq!(rdr.count(), 2);
+	assert!(CafPacketReader::from_bytes(b"nope", vec![]).is_err());
+}
+
+#[test]
+fn huge_packet_lengths() {
+	let huge = [u64::max_value(), i64::max_value() as u64, i64::max_value() as u64 - 2];
+	for &len in huge.iter() {
+		let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0),
+			pakt(1024, 0, 0, &[len]), data(0, &[1, 2, 3])]);
+		let mut rdr = CafPacketReader::from_bytes(&f, vec![]).unwrap();
+		assert_eq!(rdr.next_packet_size(), None);
+		assert!(rdr.next_packet().unwrap().is_none());
+		let mut rdr = CafPacketReader::new_lazy(Cursor::new(&f[..]), vec![]).unwrap();
+		assert!(rdr.next_packet().unwrap().is_none());
+	}
+	let table = PacketTable {
+		num_valid_frames : 0,
+		num_priming_frames : 0,
+		num_remainder_frames : 0,
+		lengths : vec![u64::max_value(), 5],
+	};
+	assert!(CafPacketReader::from_parts(Cursor::new(vec![0; 20]),
+		aac_desc(), Some(table), 20).is_err());
+}
+
+#[test]
+fn edit_count() {
+	let f = vbr_file(&[3]);
+	assert_eq!(CafPacketReader::from_bytes(&f, vec![]).unwrap().edit_count(), 1);
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16), data(0xdeadbeef, &[1, 2, 3, 4])]);
+	let mut rdr = CafPacketReader::from_bytes(&f, vec![]).unwrap();
+	assert_eq!(rdr.edit_count(), 0xdeadbeef);
+	// Not part of the packets
+	assert_eq!(rdr.next_packet().unwrap().unwrap(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn packets_remaining() {
+	let f = vbr_file(&[3, 5, 2]);
+	let mut rdr = CafPacketReader::from_bytes(&f, vec![]).unwrap();
+	assert_eq!(rdr.packets_remaining(), Some(3));
+	rdr.next_packet().unwrap();
+	assert_eq!(rdr.packets_remaining(), Some(2));
+	while rdr.next_packet().unwrap().is_some() {}
+	assert_eq!(rdr.packets_remaining(), Some(0));
+	// Unknown for unsized audio data chunks
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16), data_unsized(0, &[1, 2, 3, 4])]);
+	let rdr = CafPacketReader::from_bytes(&f, vec![]).unwrap();
+	assert_eq!(rdr.packets_remaining(), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn packet_iter_from_file() {
+	use std::fs::{self, File};
+	use std::io::{BufReader, Write};
+
+	let lengths = (0 .. 8000).map(|i| 400 + i % 300).collect::<Vec<u64>>();
+	let f = vbr_file(&lengths);
+	assert!(f.len() > 4_000_000);
+	let path = ::std::env::temp_dir().join(format!("caf-packet-iter-{}.caf", ::std::process::id()));
+	File::create(&path).unwrap().write_all(&f).unwrap();
+	drop(f);
+
+	let rdr = CafPacketReader::new(BufReader::new(File::open(&path).unwrap()), vec![]).unwrap();
+	let mut n = 0;
+	for (i, packet) in rdr.into_packet_iter().enumerate() {
+		let packet = packet.unwrap();
+		assert_eq!(packet.len() as u64, lengths[i]);
+		assert_eq!(packet[0], i as u8);
+		n += 1;
+	}
+	assert_eq!(n, 8000);
+	fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn reset() {
+	let f = vbr_file(&[3, 5, 2]);
+	for &lazy in [false, true].iter() {
+		let c = Cursor::new(f.clone());
+		let mut rdr = if lazy {
+			CafPacketReader::new_lazy(c, vec![])
+		} else {
+			CafPacketReader::new(c, vec![])
+		}.unwrap();
+		read_all(&mut rdr);
+		rdr.reset().unwrap();
+		assert_eq!(rdr.get_packet_idx(), 0);
+		assert_eq!(read_all(&mut rdr), vec![vec![0; 3], vec![1; 5], vec![2; 2]]);
+	}
+}
+
+#[test]
+fn audio_data_offset() {
+	let f = pcm_file(3);
+	let rdr = CafPacketReader::from_bytes(&f, vec![]).unwrap();
+	let info_len = info(&[("title", "x")]).len() as u64;
+	// File header, description, info, audio data chunk header and edit count
+	assert_eq!(rdr.audio_data_offset(), 8 + 12 + 32 + info_len + 12 + 4);
+	assert_eq!(&f[rdr.audio_data_offset() as usize ..][.. 4], &[0, 0, 1, 0]);
+	let f = vbr_file(&[3, 5]);
+	let rdr = CafPacketReader::from_bytes(&f, vec![]).unwrap();
+	assert_eq!(rdr.audio_data_offset() as usize, f.len() - 8);
+}
+
+#[test]
+fn seek_without_packet_table() {
+	let mut rdr = CafPacketReader::new(Cursor::new(vbr_file(&[3, 5, 2])), vec![]).unwrap();
+	rdr.packet_table = None;
+	match rdr.seek_to_packet(2) {
+		Err(CafError::Io(ref e)) if e.kind() == io::ErrorKind::InvalidData => (),
+		r => panic!("expected an invalid data error, got {:?}", r),
+	}
+	match rdr.seek_to_byte(4) {
+		Err(CafError::Io(ref e)) if e.kind() == io::ErrorKind::InvalidData => (),
+		r => panic!("expected an invalid data error, got {:?}", r),
+	}
+}
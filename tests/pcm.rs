@@ -0,0 +1,129 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+extern crate caf;
+
+mod common;
+
+use caf::io::Cursor;
+use caf::{CafPacketReader, CafError, FormatType, SampleSpec, SampleFormat, open_pcm};
+use caf::pcm::{ulaw_to_i16, alaw_to_i16, decode_packet_i16, decode_packet_f32};
+use caf::chunks::AudioDescription;
+use common::*;
+
+#[test]
+fn pcm_unsized_audio_chunk() {
+	let expected = (0 .. 10).collect::<Vec<i16>>();
+	let f = pcm_file_unsized(5);
+	let rdr = CafPacketReader::new(Cursor::new(f.clone()), vec![]).unwrap();
+	let samples = rdr.pcm_samples_iter().unwrap()
+		.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(samples, expected);
+	let samples = open_pcm(Cursor::new(f)).unwrap()
+		.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(samples, expected);
+}
+
+#[test]
+fn companded_samples() {
+	assert_eq!(ulaw_to_i16(0xff), 0);
+	assert_eq!(ulaw_to_i16(0x00), -32124);
+	assert_eq!(ulaw_to_i16(0x80), 32124);
+	assert_eq!(alaw_to_i16(0xd5), 8);
+	assert_eq!(alaw_to_i16(0x55), -8);
+	assert_eq!(alaw_to_i16(0xaa), 32256);
+
+	let f = file(&[desc(8000.0, b"ulaw", 0, 1, 1, 1, 8), data(0, &[0xff, 0x00, 0x80])]);
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	let samples = rdr.pcm_samples_iter().unwrap()
+		.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(samples, vec![0, -32124, 32124]);
+
+	let f = file(&[desc(8000.0, b"alaw", 0, 1, 1, 1, 8), data(0, &[0xd5, 0x55])]);
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![]).unwrap();
+	let samples = rdr.pcm_samples_iter().unwrap()
+		.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(samples, vec![8, -8]);
+
+	let rdr = CafPacketReader::new(Cursor::new(vbr_file(&[1])), vec![]).unwrap();
+	assert!(rdr.pcm_samples_iter().is_err());
+}
+
+fn pcm_samples(f :Vec<u8>) -> Vec<i16> {
+	open_pcm(Cursor::new(f)).unwrap()
+		.collect::<Result<Vec<_>, _>>().unwrap()
+}
+
+#[test]
+fn open_pcm_formats() {
+	let stream = open_pcm(Cursor::new(pcm_file(10))).unwrap();
+	assert_eq!(stream.spec, SampleSpec {
+		rate : 44100,
+		channels : 2,
+		format : SampleFormat::I16,
+		big_endian : false,
+	});
+	let samples = stream.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(samples, (0 .. 20).collect::<Vec<i16>>());
+
+	// 24 bit big endian
+	let payload = [0x12, 0x34, 0x56, 0xff, 0xff, 0xfe];
+	let f = file(&[desc(8000.0, b"lpcm", 0, 3, 1, 1, 24), data(0, &payload)]);
+	assert_eq!(pcm_samples(f), vec![0x1234, -1]);
+
+	// 32 bit float little endian, clamped
+	let mut payload = Vec::new();
+	for v in [0.5f32, -1.0, 2.0].iter() {
+		payload.extend_from_slice(&v.to_bits().to_le_bytes());
+	}
+	let f = file(&[desc(8000.0, b"lpcm", 3, 4, 1, 1, 32), data(0, &payload)]);
+	assert_eq!(pcm_samples(f), vec![16384, -32768, 32767]);
+
+	match open_pcm(Cursor::new(vbr_file(&[1]))) {
+		Err(CafError::UnsupportedFormat(FormatType::Mpeg4Aac)) => (),
+		_ => panic!("expected an unsupported format error"),
+	}
+}
+
+#[test]
+fn pcm_output_len() {
+	let rdr = CafPacketReader::new(Cursor::new(pcm_file(37)), vec![]).unwrap();
+	assert_eq!(rdr.pcm_output_len(), Some(74));
+	let samples = rdr.pcm_samples_iter().unwrap()
+		.collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(samples.len(), 74);
+	let rdr = CafPacketReader::new(Cursor::new(vbr_file(&[1, 2])), vec![]).unwrap();
+	assert_eq!(rdr.pcm_output_len(), None);
+}
+
+#[test]
+fn decode_packets() {
+	let desc = AudioDescription::pcm_s16le(44100.0, 2);
+	assert_eq!(decode_packet_i16(&desc, &[1, 0, 0xff, 0xff]).unwrap(), vec![1, -1]);
+	assert_eq!(decode_packet_f32(&desc, &[0, 0x40, 0, 0xc0]).unwrap(), vec![0.5, -0.5]);
+
+	// Trailing partial samples are ignored
+	let desc = AudioDescription::pcm_f32(44100.0, 2);
+	let mut packet = Vec::new();
+	packet.extend_from_slice(&0.25f32.to_bits().to_le_bytes());
+	packet.extend_from_slice(&(-1.0f32).to_bits().to_le_bytes());
+	packet.push(9);
+	assert_eq!(decode_packet_f32(&desc, &packet).unwrap(), vec![0.25, -1.0]);
+	assert_eq!(decode_packet_i16(&desc, &packet).unwrap(), vec![8192, -32768]);
+
+	let mut desc = AudioDescription::pcm_s16le(44100.0, 1);
+	desc.bits_per_channel = 12;
+	desc.bytes_per_packet = 1;
+	match decode_packet_i16(&desc, &[0]) {
+		Err(CafError::UnsupportedFormat(_)) => (),
+		r => panic!("expected an unsupported format error, got {:?}", r),
+	}
+	let mut desc = AudioDescription::pcm_s16le(44100.0, 1);
+	desc.format_id = FormatType::Mpeg4Aac;
+	assert!(decode_packet_f32(&desc, &[0, 0]).is_err());
+}
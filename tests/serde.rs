@@ -0,0 +1,42 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+#![cfg(feature = "serde")]
+
+extern crate caf;
+extern crate serde_json;
+
+mod common;
+
+use caf::io::Cursor;
+use caf::{CafPacketReader, ChunkType, FormatType};
+use caf::chunks::{AudioDescription, PacketTable};
+use common::*;
+
+#[test]
+fn serde_round_trip() {
+	let rdr = CafPacketReader::new(Cursor::new(vbr_file(&[3, 5, 7])), vec![]).unwrap();
+
+	let json = serde_json::to_string(&rdr.audio_desc).unwrap();
+	let desc :AudioDescription = serde_json::from_str(&json).unwrap();
+	assert_eq!(desc, rdr.audio_desc);
+
+	let table = rdr.packet_table.unwrap();
+	let json = serde_json::to_string(&table).unwrap();
+	let table_de :PacketTable = serde_json::from_str(&json).unwrap();
+	assert_eq!(table_de, table);
+
+	for ch_type in [ChunkType::Info, ChunkType::Other(0x41424344)].iter() {
+		let json = serde_json::to_string(ch_type).unwrap();
+		let ch_type_de :ChunkType = serde_json::from_str(&json).unwrap();
+		assert_eq!(&ch_type_de, ch_type);
+	}
+	let json = serde_json::to_string(&FormatType::Mpeg4Aac).unwrap();
+	let format :FormatType = serde_json::from_str(&json).unwrap();
+	assert_eq!(format, FormatType::Mpeg4Aac);
+}
@@ -0,0 +1,94 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+extern crate caf;
+
+use caf::{FormatType, SampleSpec, SampleFormat};
+use caf::chunks::AudioDescription;
+
+fn lpcm(format_flags :u32, bits :u32) -> AudioDescription {
+	AudioDescription {
+		sample_rate : 44100.0,
+		format_id : FormatType::LinearPcm,
+		format_flags : format_flags,
+		bytes_per_packet : bits / 8 * 2,
+		frames_per_packet : 1,
+		channels_per_frame : 2,
+		bits_per_channel : bits,
+	}
+}
+
+#[test]
+fn sample_spec_endianness() {
+	let spec = SampleSpec::from(&lpcm(0, 16));
+	assert_eq!(spec, SampleSpec {
+		rate : 44100,
+		channels : 2,
+		format : SampleFormat::I16,
+		big_endian : true,
+	});
+	assert_eq!(spec.audio_description().unwrap(), lpcm(0, 16));
+
+	let spec = SampleSpec::from(&lpcm(2, 24));
+	assert_eq!(spec.format, SampleFormat::I24);
+	assert!(!spec.big_endian);
+	assert_eq!(spec.audio_description().unwrap(), lpcm(2, 24));
+
+	let spec = SampleSpec::from(&lpcm(1, 32));
+	assert_eq!(spec.format, SampleFormat::F32);
+	assert!(spec.big_endian);
+	assert_eq!(spec.audio_description().unwrap(), lpcm(1, 32));
+
+	// Byte order doesn't matter for 8 bit samples
+	assert!(!SampleSpec::from(&lpcm(0, 8)).big_endian);
+	let mut aac = lpcm(0, 0);
+	aac.format_id = FormatType::Mpeg4Aac;
+	let spec = SampleSpec::from(&aac);
+	assert_eq!(spec.format, SampleFormat::Unknown);
+	assert!(!spec.big_endian);
+}
+
+#[test]
+fn pcm_descriptions() {
+	assert_eq!(AudioDescription::pcm_s16le(44100.0, 2), lpcm(2, 16));
+	let desc = AudioDescription::pcm_s16le(48000.0, 2);
+	assert_eq!(desc, SampleSpec {
+		rate : 48000,
+		channels : 2,
+		format : SampleFormat::I16,
+		big_endian : false,
+	}.audio_description().unwrap());
+
+	let desc = AudioDescription::pcm_f32(44100.0, 1);
+	assert_eq!(desc.format_flags, 3);
+	assert_eq!(desc.bytes_per_packet, 4);
+	assert_eq!(desc.bits_per_channel, 32);
+	assert_eq!(SampleSpec::from(&desc).format, SampleFormat::F32);
+	desc.validate().unwrap();
+}
+
+#[test]
+fn pcm_flags() {
+	let desc = AudioDescription::pcm_f32(44100.0, 2);
+	assert!(desc.pcm_is_float());
+	assert!(desc.pcm_is_little_endian());
+	let mut desc = lpcm(2, 16);
+	assert!(!desc.pcm_is_float());
+	assert!(desc.pcm_is_little_endian());
+	desc.format_flags = 0;
+	assert!(!desc.pcm_is_float());
+	assert!(!desc.pcm_is_little_endian());
+	desc.format_flags = 1;
+	assert!(desc.pcm_is_float());
+	assert!(!desc.pcm_is_little_endian());
+	// The flags mean something else for other formats
+	desc.format_id = FormatType::Mpeg4Aac;
+	desc.format_flags = 3;
+	assert!(!desc.pcm_is_float());
+	assert!(!desc.pcm_is_little_endian());
+}
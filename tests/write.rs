@@ -0,0 +1,102 @@
+// CAF container decoder written in Rust
+//
+// Copyright (c) 2017 est31 <MTest31@outlook.com>
+// and contributors. All rights reserved.
+// Licensed under MIT license, or Apache 2 license,
+// at your option. Please see the LICENSE file
+// attached to this source distribution for details.
+
+extern crate caf;
+
+mod common;
+
+use caf::io::Cursor;
+use caf::{CafPacketReader, CafChunkReader, ChunkType, CafError};
+use caf::chunks::CafChunk;
+use caf::write::{CafWriter, TwoPassWriter, update_info_in_place};
+use common::*;
+
+#[test]
+fn two_pass_writer() {
+	let lengths = [3, 5, 200, 70000];
+	let mut rdr = CafPacketReader::new(Cursor::new(vbr_file(&lengths)), vec![]).unwrap();
+	let packet_table = rdr.packet_table.clone().unwrap();
+	let mut wtr = TwoPassWriter::new(Vec::new(), rdr.audio_desc.clone());
+	wtr.add_chunk(CafChunk::Info(vec![("a".to_string(), "b".to_string())]));
+	wtr.set_edit_count(1);
+	wtr.set_frame_counts(packet_table.num_valid_frames,
+		packet_table.num_priming_frames, packet_table.num_remainder_frames);
+	let mut packets = Vec::new();
+	while let Some(packet) = rdr.next_packet().unwrap() {
+		wtr.write_packet(&packet);
+		packets.push(packet);
+	}
+	let written = wtr.finish().unwrap();
+
+	let mut payload = Vec::new();
+	for (i, l) in lengths.iter().enumerate() {
+		payload.extend(::std::iter::repeat(i as u8).take(*l as usize));
+	}
+	let expected = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0),
+		info(&[("a", "b")]),
+		pakt(4 * 1024 - 2112 - 100, 2112, 100, &lengths), data(1, &payload)]);
+	assert_eq!(written, expected);
+
+	let mut rdr = CafPacketReader::new(Cursor::new(written), vec![ChunkType::Info]).unwrap();
+	assert_eq!(rdr.edit_count, 1);
+	assert_eq!(rdr.chunks.len(), 1);
+	assert_eq!(rdr.packet_table.as_ref().unwrap().lengths, packet_table.lengths);
+	let mut read_packets = Vec::new();
+	while let Some(packet) = rdr.next_packet().unwrap() {
+		read_packets.push(packet);
+	}
+	assert_eq!(read_packets, packets);
+}
+
+fn read_info(f :Vec<u8>) -> Vec<(String, String)> {
+	let rdr = CafPacketReader::new(Cursor::new(f), vec![ChunkType::Info]).unwrap();
+	match rdr.chunks[0] {
+		CafChunk::Info(ref entries) => entries.clone(),
+		_ => panic!("expected an info chunk"),
+	}
+}
+
+#[test]
+fn update_info() {
+	let f = pcm_file(3);
+	let mut c = Cursor::new(f.clone());
+	update_info_in_place(&mut c, vec![("title".to_string(), "y".to_string())]).unwrap();
+	let updated = c.into_inner();
+	assert_eq!(updated.len(), f.len());
+	assert_eq!(read_info(updated), vec![("title".to_string(), "y".to_string())]);
+
+	// A different size is refused, and nothing is written
+	let mut c = Cursor::new(f.clone());
+	match update_info_in_place(&mut c, vec![("title".to_string(), "yy".to_string())]) {
+		Err(CafError::ChunkSizeChanged) => (),
+		_ => panic!("expected a chunk size error"),
+	}
+	assert_eq!(c.into_inner(), f);
+
+	let f = file(&[desc(44100.0, b"lpcm", 2, 4, 1, 2, 16), data(0, &[0; 8])]);
+	match update_info_in_place(&mut Cursor::new(f), vec![]) {
+		Err(CafError::MissingChunk(ChunkType::Info)) => (),
+		_ => panic!("expected a missing chunk error"),
+	}
+}
+
+#[test]
+fn chunk_round_trip() {
+	let chan = [0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1,
+		0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+	let f = file(&[desc(44100.0, b"aac ", 0, 0, 1024, 2, 0), chunk(b"chan", &chan),
+		chunk(b"kuki", &[1, 2, 3]), info(&[("a", "b"), ("title", "x")]),
+		pakt(2048, 0, 0, &[3, 300]), data(0, &[0; 303])]);
+	let mut rdr = CafChunkReader::new(Cursor::new(f.clone())).unwrap();
+	let mut wtr = CafWriter::new(Vec::new()).unwrap();
+	for _ in 0 .. 6 {
+		let ch = rdr.read_chunk().unwrap();
+		wtr.write_chunk(&ch).unwrap();
+	}
+	assert_eq!(wtr.into_inner(), f);
+}